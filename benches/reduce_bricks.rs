@@ -0,0 +1,65 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use image::{Rgba, RgbaImage};
+use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+use lego_mosaic::Mosaic;
+use lego_mosaic::ldraw::{BLUE, GREEN, LdrawBrick, LdrawColor, RED, WHITE};
+use lego_mosaic::palette::EuclideanDistancePalette;
+
+const PLATE_SIZES: [(u8, u8); 8] = [(1, 2), (1, 3), (1, 4), (2, 2), (2, 3), (2, 4), (4, 4), (6, 6)];
+
+fn plates() -> Vec<LdrawBrick<&'static str, u8>> {
+    PLATE_SIZES.iter().map(|&(length, width)| LdrawBrick {
+        id: "plate",
+        length,
+        width,
+        height: 1,
+        unit_brick: 0,
+        rotated: false
+    }).collect()
+}
+
+fn single_color_mosaic(size: u32, height: u32) -> Mosaic<u8, LdrawBrick<&'static str, u8>, LdrawColor> {
+    let img = RgbaImage::from_pixel(size, size, Rgba([255, 255, 255, 255]));
+    let palette = EuclideanDistancePalette::new(&[WHITE]);
+
+    Mosaic::from_image(&img, &palette, |_, _, _| height, |_, _, _, _| 0).unwrap()
+}
+
+fn random_color_mosaic(size: u32) -> Mosaic<u8, LdrawBrick<&'static str, u8>, LdrawColor> {
+    let colors = [WHITE, RED, GREEN, BLUE];
+    let mut rng = ChaCha8Rng::seed_from_u64(1705276380);
+    let img = RgbaImage::from_fn(size, size, |_, _| {
+        let color = colors[rng.gen_range(0..colors.len())].value;
+        Rgba([color.red, color.green, color.blue, color.alpha])
+    });
+    let palette = EuclideanDistancePalette::new(&colors);
+
+    Mosaic::from_image(&img, &palette, |_, _, _| 1, |_, _, _, _| 0).unwrap()
+}
+
+fn bench_reduce_bricks(c: &mut Criterion) {
+    let bricks = plates();
+
+    let background = single_color_mosaic(255, 1);
+    c.bench_function("reduce 255x255 single color", |b| b.iter(
+        || black_box(background.clone()).reduce_bricks(&bricks, &[]).unwrap()
+    ));
+
+    let relief = single_color_mosaic(128, 8);
+    c.bench_function("reduce 128x128x8 single color", |b| b.iter(
+        || black_box(relief.clone()).reduce_bricks(&bricks, &[]).unwrap()
+    ));
+
+    let noise = random_color_mosaic(255);
+    c.bench_function("reduce 255x255 random colors", |b| b.iter(
+        || black_box(noise.clone()).reduce_bricks(&bricks, &[]).unwrap()
+    ));
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = bench_reduce_bricks
+}
+criterion_main!(benches);
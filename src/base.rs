@@ -1,39 +1,99 @@
+use std::collections::{BTreeMap, BTreeSet};
 use std::iter;
-use crate::{Brick, Color, NonUnitBrick, PlacedBrick, UnitBrick};
-use crate::BaseError::{NotATwoByOnePlate, NotATwoByTwoPlate};
+use std::ops::Range;
+use crate::{points_in_box, Brick, Color, ConnectivityGraph, Footprint, Groups, Image, Mosaic, NonUnitBrick, Palette, PlacedBrick, Seam, SeamDirection, UnitBrick, ValidationIssue};
+use crate::BaseError::{HangerDisconnectsBase, HangerDoesNotFit, NotABaseplate, NotATwoByOnePlate, NotATwoByTwoPlate, SeamThroughAllLayers, UnjoinedFootprint, ZeroHeight};
 
 // ====================
 // PUBLIC STRUCTS
 // ====================
 
+/* SeamThroughAllLayers is checked for every base of two or more layers, with two exceptions for
+   two-layer bases, which keep their original layout. Seams through both layers in the outermost
+   row or column of studs are allowed, and hangers, which take the place of bricks in the bottom
+   layer, are only checked by HangerDisconnectsBase. Bases of three or more layers have no
+   exceptions, with or without hangers. Base::new builds the same two layers as with_height but
+   reports a seam through both layers as a warning instead, as it did before seams were checked. */
 #[non_exhaustive]
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub enum BaseError<B> {
     NotATwoByOnePlate(B),
-    NotATwoByTwoPlate(B)
+    NotATwoByTwoPlate(B),
+    NotABaseplate(B),
+    ZeroHeight,
+    SeamThroughAllLayers(u32, u32),
+    HangerDoesNotFit(B),
+    HangerDisconnectsBase(u32, u32),
+    UnjoinedFootprint(u32, u32)
+}
+
+/* Bricks in ZeroSizedBrick and NotAPlate are left out of the base, while ZeroSize still builds an
+   empty base. SeamThroughAllLayers only comes from Base::new and gives the first seam found. */
+#[non_exhaustive]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum BaseWarning<B> {
+    ZeroSize,
+    ZeroSizedBrick(B),
+    NotAPlate(B),
+    SeamThroughAllLayers(u32, u32)
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum BridgeLayer {
+    Below,
+    Above
+}
+
+/* A row of hangers set into the bottom layer of the base, from_top studs south of the top edge
+   and spacing studs apart along l. A spacing of zero places a single hanger in the middle. The
+   brick can be a Technic brick with a hole or a bracket, placed as given, so rotate it to point
+   the hole the right way. */
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Hangers<B> {
+    pub brick: B,
+    pub from_top: u32,
+    pub spacing: u32
 }
 
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
 pub struct Base<U, B, C> {
-    base_bricks: Vec<FilledArea<U, B>>,
-    support_bricks: Vec<FilledArea<U, B>>,
+    layers: Vec<Vec<FilledArea<U, B>>>,
     color: C,
+    brick_colors: BTreeMap<(u32, u32, u32), C>,
     length: u32,
-    width: u32
+    width: u32,
+    baseplate_count: u32,
+    joiner_count: u32,
+    warnings: Vec<BaseWarning<B>>
 }
 
 impl<U: UnitBrick, B: NonUnitBrick<U>, C: Color> Base<U, B, C> {
 
     pub fn new(length: u32, width: u32, color: C, one_by_one: U, two_by_one: B, two_by_two: B, other_bricks: &[B]) -> Result<Base<U, B, C>, BaseError<B>> {
-        let mut two_by_one = two_by_one;
-        if two_by_one.length() == 1 && two_by_one.width() == 2 {
-            two_by_one = two_by_one.rotate_90();
-        } else if two_by_one.length() != 2 || two_by_one.width() != 1 || two_by_one.height() != 1 {
-            return Err(NotATwoByOnePlate(two_by_one));
-        }
+        Base::build(length, width, 2, color, one_by_one, two_by_one, two_by_two, other_bricks, None, false)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_height(length: u32, width: u32, height: u32, color: C, one_by_one: U,
+                       two_by_one: B, two_by_two: B, other_bricks: &[B]) -> Result<Base<U, B, C>, BaseError<B>> {
+        Base::build(length, width, height, color, one_by_one, two_by_one, two_by_two, other_bricks, None, true)
+    }
+
+    /* Hangers taller than a plate stick out below the bottom layer, so the base grows by the
+       extra height and everything else moves up to make room */
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_hangers(length: u32, width: u32, height: u32, color: C, one_by_one: U, two_by_one: B,
+                        two_by_two: B, other_bricks: &[B], hangers: Hangers<B>) -> Result<Base<U, B, C>, BaseError<B>> {
+        Base::build(length, width, height, color, one_by_one, two_by_one, two_by_two, other_bricks, Some(hangers), true)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn build(length: u32, width: u32, height: u32, color: C, one_by_one: U, two_by_one: B, two_by_two: B,
+             other_bricks: &[B], hangers: Option<Hangers<B>>, seams_are_errors: bool) -> Result<Base<U, B, C>, BaseError<B>> {
+        let two_by_one = check_plates(two_by_one, two_by_two)?;
 
-        if two_by_two.length() != 2 || two_by_two.width() != 2 || two_by_two.height() != 1 {
-            return Err(NotATwoByTwoPlate(two_by_two));
+        if height == 0 {
+            return Err(ZeroHeight);
         }
 
         let mut even_by_one_bricks = vec![Brick::NonUnit(two_by_one)];
@@ -66,56 +126,17 @@ impl<U: UnitBrick, B: NonUnitBrick<U>, C: Color> Base<U, B, C> {
         sort_by_area(&mut even_by_one_bricks);
         sort_by_area(&mut even_by_even_bricks);
 
-        let even_length = make_even(length);
-        let even_width = make_even(width);
-        let mut base_bricks = fill(
+        let base_bricks = tile(
             0,
             0,
-            even_length,
-            even_width,
-            0,
-            &even_by_even_bricks
+            length,
+            width,
+            one_by_one,
+            &even_by_even_bricks,
+            &one_by_even_bricks,
+            &even_by_one_bricks
         );
 
-        let is_odd_length = length != even_length;
-        let is_odd_width = width != even_width;
-
-        if is_odd_length {
-            let mut areas_right = fill(
-                even_length,
-                0,
-                1,
-                even_width,
-                0,
-                &one_by_even_bricks
-            );
-
-            base_bricks.append(&mut areas_right);
-        }
-
-        if is_odd_width {
-            let mut areas_below = fill(
-                0,
-                even_width,
-                even_length,
-                1,
-                0,
-                &even_by_one_bricks
-            );
-
-            base_bricks.append(&mut areas_below);
-        }
-
-        if is_odd_length && is_odd_width {
-            base_bricks.push(FilledArea {
-                brick: Brick::Unit(one_by_one),
-                l: even_length,
-                w: even_width,
-                length: 1,
-                width: 1
-            });
-        }
-
         let support_bricks = Base::<U, B, C>::build_supports(
             &base_bricks,
             one_by_one,
@@ -126,17 +147,319 @@ impl<U: UnitBrick, B: NonUnitBrick<U>, C: Color> Base<U, B, C> {
             width
         );
 
+        /* Every seam in the base layer lies on an even l or w, so the same layout shifted one
+           stud east and south has no seams in common with it. Below the top two layers, shifted
+           layers alternate with supports, so no seam can run through all of them. */
+        let shifted_bricks = match height > 2 && length > 0 && width > 0 {
+            true => {
+                let mut column_bricks = one_by_even_bricks.clone();
+                sort_by_area(&mut column_bricks);
+                column_bricks.push(Brick::Unit(one_by_one));
+
+                let mut row_bricks = even_by_one_bricks.clone();
+                row_bricks.push(Brick::Unit(one_by_one));
+
+                let mut shifted_bricks = vec![FilledArea {
+                    brick: Brick::Unit(one_by_one),
+                    l: 0,
+                    w: 0,
+                    length: 1,
+                    width: 1
+                }];
+                shifted_bricks.append(&mut fill(0, 1, 1, width - 1, 0, &column_bricks));
+                shifted_bricks.append(&mut fill(1, 0, length - 1, 1, 0, &row_bricks));
+                shifted_bricks.append(&mut tile(
+                    1,
+                    1,
+                    length - 1,
+                    width - 1,
+                    one_by_one,
+                    &even_by_even_bricks,
+                    &one_by_even_bricks,
+                    &even_by_one_bricks
+                ));
+
+                shifted_bricks
+            },
+            false => Vec::new()
+        };
+
+        let layers = (0..height).rev()
+            .map(|depth| match depth {
+                0 => base_bricks.clone(),
+                _ if !is_even(depth) => support_bricks.clone(),
+                _ => shifted_bricks.clone()
+            })
+            .collect();
+
+        let mut base = Base {
+            layers,
+            color,
+            brick_colors: BTreeMap::new(),
+            length,
+            width,
+            baseplate_count: 0,
+            joiner_count: 0,
+            warnings: base_warnings(length, width, other_bricks)
+        };
+
+        let is_allowed = |seam: &Seam| height == 1 || (height == 2 && is_edge_seam(seam, length, width));
+        if let Some(seam) = base.through_seams().into_iter().find(|seam| !is_allowed(seam)) {
+            match seams_are_errors {
+                true => return Err(SeamThroughAllLayers(seam.l, seam.w)),
+                false => base.warnings.push(BaseWarning::SeamThroughAllLayers(seam.l, seam.w))
+            }
+        }
+
+        if let Some(hangers) = hangers {
+            base.add_hangers(hangers, &plates(one_by_one, two_by_one, two_by_two, other_bricks))?;
+
+            if let Some(seam) = base.through_seams().into_iter().find(|_| height > 2) {
+                return Err(SeamThroughAllLayers(seam.l, seam.w));
+            }
+        }
+
+        Ok(base)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_baseplates(length: u32, width: u32, color: C, baseplates: &[B], bridge_layer: BridgeLayer, one_by_one: U,
+                           two_by_one: B, two_by_two: B, other_bricks: &[B]) -> Result<Base<U, B, C>, BaseError<B>> {
+        let two_by_one = check_plates(two_by_one, two_by_two)?;
+
+        // Seams at least four studs apart leave room for a joiner strip on both sides of every baseplate
+        if let Some(&baseplate) = baseplates.iter()
+            .find(|baseplate| baseplate.length() < 4 || baseplate.width() < 4 || baseplate.height() != 1) {
+            return Err(NotABaseplate(baseplate));
+        }
+
+        let plates = plates(one_by_one, two_by_one, two_by_two, other_bricks);
+        let length_two_plates: BrickVec<U, B> = plates.iter().copied().filter(|plate| plate.length() == 2).collect();
+        let width_two_plates: BrickVec<U, B> = plates.iter().copied().filter(|plate| plate.width() == 2).collect();
+
+        let baseplate = baseplates.iter()
+            .flat_map(|&baseplate| iter::once(baseplate).chain(
+                iter::once(baseplate.rotate_90()).filter(|rotated| rotated.length() != rotated.width())
+            ))
+            .filter(|baseplate| baseplate.length() as u32 <= length && baseplate.width() as u32 <= width)
+            .max_by_key(|baseplate| baseplate.length() as u32 * baseplate.width() as u32);
+        let columns = segments(length, baseplate.map_or(length, |baseplate| baseplate.length() as u32));
+        let rows = segments(width, baseplate.map_or(width, |baseplate| baseplate.width() as u32));
+
+        let mut plate_bricks = Vec::new();
+        let mut bridge_bricks = Vec::new();
+        let mut baseplate_count = 0;
+
+        /* Baseplates are laid in a grid, and cells along the east and south edges that are too
+           small for a baseplate are filled with plates instead. The bridging layer covers the
+           inside of every cell that needs it, leaving a two-stud strip over each seam. */
+        for &(l, cell_length) in &columns {
+            for &(w, cell_width) in &rows {
+                let cell_baseplate = baseplate
+                    .filter(|baseplate| baseplate.length() as u32 == cell_length && baseplate.width() as u32 == cell_width);
+
+                match cell_baseplate {
+                    Some(baseplate) => {
+                        plate_bricks.push(FilledArea {
+                            brick: Brick::NonUnit(baseplate),
+                            l,
+                            w,
+                            length: cell_length,
+                            width: cell_width
+                        });
+                        baseplate_count += 1;
+                    },
+                    None => plate_bricks.append(&mut fill(l, w, cell_length, cell_width, 0, &plates))
+                }
+
+                // Plates filling a cell have seams of their own, so a lower bridging layer must cover them
+                if bridge_layer == BridgeLayer::Above || cell_baseplate.is_none() {
+                    let mut min_l = l + (l > 0) as u32;
+                    let mut min_w = w + (w > 0) as u32;
+                    let max_l = l + cell_length - (l + cell_length < length) as u32;
+                    let max_w = w + cell_width - (w + cell_width < width) as u32;
+
+                    // Start the plates one stud inside the cell so their seams do not line up with the plates in the cell
+                    if cell_baseplate.is_none() && l == 0 && max_l > min_l && max_w > min_w {
+                        bridge_bricks.append(&mut fill(min_l, min_w, 1, max_w - min_w, 0, &plates));
+                        min_l += 1;
+                    }
+                    if cell_baseplate.is_none() && w == 0 && max_l > min_l && max_w > min_w {
+                        bridge_bricks.append(&mut fill(min_l, min_w, max_l - min_l, 1, 0, &plates));
+                        min_w += 1;
+                    }
+
+                    if max_l > min_l && max_w > min_w {
+                        bridge_bricks.append(&mut fill(min_l, min_w, max_l - min_l, max_w - min_w, 0, &plates));
+                    }
+                }
+            }
+        }
+
+        /* Joiners along the east seams restart one stud before each south seam, so the joiner at
+           every crossing covers the corners of all four cells around it */
+        let mut joiners = Vec::new();
+        for &(l, _) in columns.iter().skip(1) {
+            let mut min_w = 0;
+
+            for max_w in rows.iter().skip(1).map(|&(w, _)| w - 1).chain(iter::once(width)) {
+                joiners.append(&mut fill(l - 1, min_w, 2, max_w - min_w, 0, &length_two_plates));
+                min_w = max_w;
+            }
+        }
+
+        // Joiners along the south seams run between the joiners along the east seams
+        for &(w, _) in rows.iter().skip(1) {
+            let mut min_l = 0;
+
+            for max_l in columns.iter().skip(1).map(|&(l, _)| l - 1).chain(iter::once(length)) {
+                if max_l > min_l {
+                    joiners.append(&mut fill(min_l, w - 1, max_l - min_l, 2, 0, &width_two_plates));
+                }
+
+                min_l = max_l + 2;
+            }
+        }
+
+        let joiner_count = joiners.iter().map(|joiner| joiner.brick_count()).sum();
+        bridge_bricks.append(&mut joiners);
+
+        let (base_bricks, support_bricks) = match bridge_layer {
+            BridgeLayer::Below => (plate_bricks, bridge_bricks),
+            BridgeLayer::Above => (bridge_bricks, plate_bricks)
+        };
+
         Ok(Base {
-            base_bricks,
-            support_bricks,
+            layers: vec![support_bricks, base_bricks],
             color,
+            brick_colors: BTreeMap::new(),
             length,
-            width
+            width,
+            baseplate_count,
+            joiner_count,
+            warnings: base_warnings(length, width, other_bricks)
+        })
+    }
+
+    /* Both layers cover exactly the studs inside the footprint. The top layer is laid on a grid
+       of 2x2 blocks and the bottom layer on the same grid shifted one stud east and south, so
+       every block overlaps four blocks in the other layer. Studs in blocks cut by the outline are
+       covered by smaller plates, chosen to join the bricks above them. */
+    pub fn with_footprint(footprint: &Footprint, color: C, one_by_one: U, two_by_one: B, two_by_two: B,
+                          other_bricks: &[B]) -> Result<Base<U, B, C>, BaseError<B>> {
+        let two_by_one = check_plates(two_by_one, two_by_two)?;
+
+        let mut even_by_two_bricks = vec![Brick::NonUnit(two_by_two)];
+        for &brick in other_bricks {
+            if brick.height() != 1 || brick.length() == 0 || brick.width() == 0 {
+                continue;
+            }
+
+            if brick.width() == 2 && is_even(brick.length() as u32) {
+                even_by_two_bricks.push(Brick::NonUnit(brick));
+            } else if brick.length() == 2 && is_even(brick.width() as u32) {
+                even_by_two_bricks.push(Brick::NonUnit(brick.rotate_90()));
+            }
+        }
+        sort_by_area(&mut even_by_two_bricks);
+
+        let base_bricks = footprint_layer(footprint, 0, one_by_one, two_by_one, two_by_two, &even_by_two_bricks, &[]);
+        let support_bricks = footprint_layer(footprint, 1, one_by_one, two_by_one, two_by_two, &even_by_two_bricks, &base_bricks);
+
+        let layers = join_parts(footprint, [support_bricks, base_bricks], 0..2, one_by_one, two_by_one, two_by_two);
+
+        // Separate islands in the footprint make separate parts, but each island must hold together
+        if let Some((l, w)) = unjoined_stud(footprint, &layers) {
+            return Err(UnjoinedFootprint(l, w));
+        }
+
+        Ok(Base {
+            layers: layers.into(),
+            color,
+            brick_colors: BTreeMap::new(),
+            length: footprint.length(),
+            width: footprint.width(),
+            baseplate_count: 0,
+            joiner_count: 0,
+            warnings: base_warnings(footprint.length(), footprint.width(), other_bricks)
+        })
+    }
+
+    /* The color function is given the l, w and layer of each stud, counting layers up from the
+       bottom. Plates with studs of more than one color are split into plates of one color each,
+       placed to join the bricks in the layer below where they can. Other bricks, such as hangers,
+       are never split and take the color of their first stud. */
+    pub fn recolor(self, color_fn: impl Fn(u32, u32, u32) -> C, one_by_one: U, two_by_one: B, two_by_two: B) -> Result<Base<U, B, C>, BaseError<B>> {
+        let two_by_one = check_plates(two_by_one, two_by_two)?;
+
+        let old_layers: Vec<BrickAreas<U, B>> = self.layers.iter()
+            .map(|areas| areas.iter().flat_map(|area| area.bricks()).collect())
+            .collect();
+        let mut layers = Vec::new();
+        let mut brick_colors = BTreeMap::new();
+
+        for (h, areas) in old_layers.iter().enumerate() {
+            let other = match h {
+                0 => old_layers.get(1),
+                _ => old_layers.get(h - 1)
+            }.map_or(&[][..], |other| &other[..]);
+            let mut layer = Vec::new();
+
+            for area in areas {
+                let mut cells_by_color: Vec<(C, BTreeSet<(u32, u32)>)> = Vec::new();
+                for (l, w) in area.cells() {
+                    let color = color_fn(l, w, h as u32);
+                    match cells_by_color.iter_mut().find(|(other_color, _)| *other_color == color) {
+                        Some((_, cells)) => { cells.insert((l, w)); },
+                        None => cells_by_color.push((color, BTreeSet::from([(l, w)])))
+                    }
+                }
+
+                if cells_by_color.len() == 1 || area.brick.height() != 1 {
+                    brick_colors.insert((area.l, area.w, h as u32), cells_by_color[0].0);
+                    layer.push(*area);
+                    continue;
+                }
+
+                let other_bricks: BrickAreas<U, B> = other.iter().copied().filter(|other| other.overlaps(area)).collect();
+                for (color, cells) in cells_by_color {
+                    for piece in cover_studs(cells, &[], &other_bricks, one_by_one, two_by_one, two_by_two) {
+                        brick_colors.insert((piece.l, piece.w, h as u32), color);
+                        layer.push(piece);
+                    }
+                }
+            }
+
+            layers.push(layer);
+        }
+
+        Ok(Base {
+            layers,
+            brick_colors,
+            ..self
         })
     }
 
+    // The image colors the top layer, and every other stud is given the hidden color
+    pub fn recolor_with_image(self, image: &impl Image, palette: &impl Palette<C>, hidden_color: C, one_by_one: U,
+                              two_by_one: B, two_by_two: B) -> Result<Base<U, B, C>, BaseError<B>> {
+        let top_h = self.height().saturating_sub(1);
+
+        self.recolor(
+            |l, w, h| match h == top_h && l < image.length() && w < image.width() {
+                true => palette.nearest(image.pixel(l, w)).unwrap_or(hidden_color),
+                false => hidden_color
+            },
+            one_by_one,
+            two_by_one,
+            two_by_two
+        )
+    }
+
     pub fn iter(&self) -> impl Iterator<Item=PlacedBrick<U, B, C>> + '_ {
-        self.layer_iter(&self.support_bricks, 0).chain(self.layer_iter(&self.base_bricks, 1))
+        self.layers.iter()
+            .enumerate()
+            .flat_map(|(h, layer)| self.layer_iter(layer, h as u32))
     }
 
     pub fn length(&self) -> u32 {
@@ -148,7 +471,52 @@ impl<U: UnitBrick, B: NonUnitBrick<U>, C: Color> Base<U, B, C> {
     }
 
     pub fn height(&self) -> u32 {
-        2
+        self.layers.len() as u32
+    }
+
+    pub fn baseplate_count(&self) -> u32 {
+        self.baseplate_count
+    }
+
+    pub fn joiner_count(&self) -> u32 {
+        self.joiner_count
+    }
+
+    pub fn warnings(&self) -> &[BaseWarning<B>] {
+        &self.warnings
+    }
+
+    /* Layers do not have to cover the whole base, since bases with footprints or hangers leave
+       some studs empty, so only bricks that overlap or leave the base are reported, along with
+       colors that were given to a brick that is not there */
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        let mut cover_counts = BTreeMap::new();
+        let mut origins = BTreeSet::new();
+
+        for brick in self.iter() {
+            let (length, width, height) = (brick.brick.length() as u32, brick.brick.width() as u32, brick.brick.height() as u32);
+
+            if brick.l + length > self.length || brick.w + width > self.width {
+                issues.push(ValidationIssue::OutOfBounds(brick.l, brick.w, brick.h));
+            }
+
+            for point in points_in_box(brick.l, brick.w, brick.h, length, width, height) {
+                *cover_counts.entry(point).or_insert(0) += 1;
+            }
+
+            origins.insert((brick.l, brick.w, brick.h));
+        }
+
+        issues.extend(cover_counts.into_iter()
+            .filter(|&(_, count)| count > 1)
+            .map(|((l, w, h), _)| ValidationIssue::OverlappingBricks(l, w, h)));
+        issues.extend(self.brick_colors.keys()
+            .filter(|origin| !origins.contains(origin))
+            .map(|&(l, w, h)| ValidationIssue::StrayColor(l, w, h)));
+
+        issues.sort();
+        issues
     }
 
     fn build_supports(base_bricks: &[FilledArea<U, B>], one_by_one: U, two_by_one: B, two_by_two: B,
@@ -205,7 +573,56 @@ impl<U: UnitBrick, B: NonUnitBrick<U>, C: Color> Base<U, B, C> {
             .collect()
     }
 
-    fn layer_iter<'a>(&'a self, bricks: &'a [FilledArea<U, B>], h: u32) -> impl Iterator<Item=PlacedBrick<U, B, C>> + '_ {
+    fn add_hangers(&mut self, hangers: Hangers<B>, plates: &[Brick<U, B>]) -> Result<(), BaseError<B>> {
+        let hanger_length = hangers.brick.length() as u32;
+        let hanger_width = hangers.brick.width() as u32;
+        let hanger_height = hangers.brick.height() as u32;
+
+        if hanger_length == 0 || hanger_width == 0 || hanger_height == 0 || hanger_length > self.length
+            || hangers.from_top + hanger_width > self.width || (hangers.spacing > 0 && hangers.spacing < hanger_length) {
+            return Err(HangerDoesNotFit(hangers.brick));
+        }
+
+        let count = match hangers.spacing {
+            0 => 1,
+            _ => (self.length - hanger_length) / hangers.spacing + 1
+        };
+        let min_l = (self.length - (count - 1) * hangers.spacing - hanger_length) / 2;
+
+        // The hangers' tops are level with the top of the bottom layer
+        for _ in 1..hanger_height {
+            self.layers.insert(0, Vec::new());
+        }
+        let bottom = hanger_height as usize - 1;
+
+        let mut components = ConnectivityGraph::new(self.iter()).components().len();
+
+        for index in 0..count {
+            let hanger = FilledArea {
+                brick: Brick::NonUnit(hangers.brick),
+                l: min_l + index * hangers.spacing,
+                w: hangers.from_top,
+                length: hanger_length,
+                width: hanger_width
+            };
+
+            self.layers[bottom] = self.layers[bottom].iter()
+                .flat_map(|area| area.carve(&hanger, plates))
+                .collect();
+            self.layers[0].push(hanger);
+
+            // Every hanger must leave the base in no more pieces than before
+            let new_components = ConnectivityGraph::new(self.iter()).components().len();
+            if new_components > components {
+                return Err(HangerDisconnectsBase(hanger.l, hanger.w));
+            }
+            components = new_components;
+        }
+
+        Ok(())
+    }
+
+    fn layer_iter<'a>(&'a self, bricks: &'a [FilledArea<U, B>], h: u32) -> impl Iterator<Item=PlacedBrick<U, B, C>> + 'a {
         bricks.iter().flat_map(move |area|
             (area.l..(area.l + area.length)).step_by(area.brick.length() as usize).flat_map(move |l|
                 (area.w..(area.w + area.width)).step_by(area.brick.width() as usize).map(move |w| PlacedBrick {
@@ -213,7 +630,7 @@ impl<U: UnitBrick, B: NonUnitBrick<U>, C: Color> Base<U, B, C> {
                     w,
                     h,
                     brick: area.brick,
-                    color: self.color,
+                    color: self.brick_colors.get(&(l, w, h)).copied().unwrap_or(self.color),
                 })
             )
         )
@@ -221,104 +638,603 @@ impl<U: UnitBrick, B: NonUnitBrick<U>, C: Color> Base<U, B, C> {
 
 }
 
+impl<U: UnitBrick, B: NonUnitBrick<U>, C: Color> Mosaic<U, B, C> {
+
+    /* The plates in the bottom layer of a mosaic several plates thick are replaced by plates of
+       the support color, placed to join the bricks in the layer above them, so the mosaic holds
+       together without a separate base. Taller bricks that start in the bottom layer are kept,
+       and so are the colors of studs with nothing above them, since they are part of the picture.
+       Plates reaching over those studs are split into unit bricks there. Some pictures cannot be
+       joined by one layer of plates, so check the result with a ConnectivityGraph if it must hold
+       together. Reducing the mosaic again would lay the support layer out like any other chunk,
+       so add the support layer after reducing. */
+    pub fn with_support_layer(self, color: C, one_by_one: U, two_by_one: B, two_by_two: B) -> Result<Mosaic<U, B, C>, BaseError<B>> {
+        let two_by_one = check_plates(two_by_one, two_by_two)?;
+        let to_area = |brick: PlacedBrick<U, B, C>| FilledArea {
+            brick: brick.brick,
+            l: brick.l,
+            w: brick.w,
+            length: brick.brick.length() as u32,
+            width: brick.brick.width() as u32
+        };
+
+        let above: BrickAreas<U, B> = self.iter_layer(1).map(to_area).collect();
+        let covered: BTreeSet<(u32, u32)> = above.iter().flat_map(|area| area.cells()).collect();
+        let free: BTreeSet<(u32, u32)> = self.iter_layer(0)
+            .filter(|brick| brick.h == 0 && brick.brick.height() == 1)
+            .flat_map(|brick| to_area(brick).cells().collect::<Vec<_>>())
+            .filter(|cell| covered.contains(cell))
+            .collect();
+
+        let footprint = Footprint::from_fn(self.length(), self.width(), |l, w| free.contains(&(l, w)));
+        let support_areas = cover_studs(free.clone(), &[], &above, one_by_one, two_by_one, two_by_two);
+        let [support_areas, _] = join_parts(&footprint, [support_areas, above], 0..1, one_by_one, two_by_one, two_by_two);
+
+        let support_bricks = support_areas.into_iter()
+            .map(|area| PlacedBrick { l: area.l, w: area.w, h: 0, brick: area.brick, color })
+            .collect();
+
+        let (length, width) = (self.length(), self.width());
+        Ok(self.transform(
+            length,
+            width,
+            |l, w, h| match h == 0 && free.contains(&(l, w)) {
+                true => None,
+                false => Some((l, w, h))
+            },
+            Some
+        ).add_bricks(one_by_one, color, support_bricks))
+    }
+}
+
 // ====================
 // PRIVATE TYPE ALIASES
 // ====================
 
 type BrickVec<U, B> = Vec<Brick<U, B>>;
+type BrickAreas<U, B> = Vec<FilledArea<U, B>>;
+type StudIds = BTreeMap<(u32, u32), usize>;
 
 // ====================
 // PRIVATE FUNCTIONS
 // ====================
 
-fn is_even(n: u32) -> bool {
-    n % 2 == 0
-}
+fn check_plates<U: UnitBrick, B: NonUnitBrick<U>>(two_by_one: B, two_by_two: B) -> Result<B, BaseError<B>> {
+    let mut two_by_one = two_by_one;
+    if two_by_one.length() == 1 && two_by_one.width() == 2 {
+        two_by_one = two_by_one.rotate_90();
+    } else if two_by_one.length() != 2 || two_by_one.width() != 1 || two_by_one.height() != 1 {
+        return Err(NotATwoByOnePlate(two_by_one));
+    }
 
-fn make_even(n: u32) -> u32 {
-    n & !1
-}
+    if two_by_two.length() != 2 || two_by_two.width() != 2 || two_by_two.height() != 1 {
+        return Err(NotATwoByTwoPlate(two_by_two));
+    }
 
-fn sub_at_most(n: u32, amount: u32) -> u32 {
-    n - n.min(amount)
+    Ok(two_by_one)
 }
 
-fn sort_by_area<U: UnitBrick, B: NonUnitBrick<U>>(bricks: &mut [Brick<U, B>]) {
-    bricks.sort_by(|brick1, brick2| {
-        let area1= brick1.length() as u16 * brick1.width() as u16;
-        let area2 = brick2.length() as u16 * brick2.width() as u16;
+#[allow(clippy::too_many_arguments)]
+fn tile<U: UnitBrick, B: NonUnitBrick<U>>(min_l: u32, min_w: u32, length: u32, width: u32, one_by_one: U, even_by_even_bricks: &[Brick<U, B>],
+                                          one_by_even_bricks: &[Brick<U, B>], even_by_one_bricks: &[Brick<U, B>]) -> Vec<FilledArea<U, B>> {
+    let even_length = make_even(length);
+    let even_width = make_even(width);
+    let mut areas = fill(
+        min_l,
+        min_w,
+        even_length,
+        even_width,
+        0,
+        even_by_even_bricks
+    );
+
+    let is_odd_length = length != even_length;
+    let is_odd_width = width != even_width;
+
+    if is_odd_length {
+        let mut areas_right = fill(
+            min_l + even_length,
+            min_w,
+            1,
+            even_width,
+            0,
+            one_by_even_bricks
+        );
 
-        // Sort in descending order
-        area2.cmp(&area1)
+        areas.append(&mut areas_right);
+    }
 
-    });
-}
+    if is_odd_width {
+        let mut areas_below = fill(
+            min_l,
+            min_w + even_width,
+            even_length,
+            1,
+            0,
+            even_by_one_bricks
+        );
 
-fn fill<U: UnitBrick, B: NonUnitBrick<U>>(min_l: u32, min_w: u32, length: u32, width: u32, min_index: usize, bricks: &[Brick<U, B>]) -> Vec<FilledArea<U, B>> {
-    let mut remaining_length = length;
-    let mut remaining_width = width;
+        areas.append(&mut areas_below);
+    }
 
-    let mut new_areas = Vec::new();
+    if is_odd_length && is_odd_width {
+        areas.push(FilledArea {
+            brick: Brick::Unit(one_by_one),
+            l: min_l + even_length,
+            w: min_w + even_width,
+            length: 1,
+            width: 1
+        });
+    }
 
-    let mut filled_length = 0;
-    let mut filled_width = 0;
+    areas
+}
 
-    let mut index = min_index;
-    while index < bricks.len() {
-        let brick = bricks[index];
-        if brick.length() as u32 <= remaining_length && brick.width() as u32 <= remaining_width {
-            remaining_length %= brick.length() as u32;
-            remaining_width %= brick.width() as u32;
+// A seam in the outermost row or column of studs, which splits that one-stud strip
+fn is_edge_seam(seam: &Seam, length: u32, width: u32) -> bool {
+    match seam.direction {
+        SeamDirection::East => seam.w == 0 || seam.w == width - 1,
+        SeamDirection::South => seam.l == 0 || seam.l == length - 1
+    }
+}
 
-            filled_length = length - remaining_length;
-            filled_width = width - remaining_width;
-            new_areas.push(FilledArea {
-                brick,
-                l: min_l,
-                w: min_w,
-                length: filled_length,
-                width: filled_width
-            });
+fn base_warnings<U: UnitBrick, B: NonUnitBrick<U>>(length: u32, width: u32, other_bricks: &[B]) -> Vec<BaseWarning<B>> {
+    let mut warnings = Vec::new();
+    if length == 0 || width == 0 {
+        warnings.push(BaseWarning::ZeroSize);
+    }
 
-            break;
+    for &brick in other_bricks {
+        if brick.length() == 0 || brick.width() == 0 || brick.height() == 0 {
+            warnings.push(BaseWarning::ZeroSizedBrick(brick));
+        } else if brick.height() != 1 {
+            warnings.push(BaseWarning::NotAPlate(brick));
         }
-
-        index += 1;
     }
 
-    // Fill following regions with next largest brick
-    index += 1;
-
-    if filled_length > 0 && remaining_width > 0 {
-        let mut areas_below = fill(
-            min_l,
-            min_w + filled_width,
-            filled_length,
-            remaining_width,
-            index,
-            bricks
-        );
-        new_areas.append(&mut areas_below);
-    }
+    warnings
+}
 
-    if remaining_length > 0 && width > 0 {
-        let mut areas_right = fill(
-            min_l + filled_length,
-            min_w,
-            remaining_length,
-            width,
-            index,
-            bricks
-        );
-        new_areas.append(&mut areas_right);
+fn plates<U: UnitBrick, B: NonUnitBrick<U>>(one_by_one: U, two_by_one: B, two_by_two: B, other_bricks: &[B]) -> BrickVec<U, B> {
+    let mut plates = vec![
+        Brick::Unit(one_by_one),
+        Brick::NonUnit(two_by_one),
+        Brick::NonUnit(two_by_one.rotate_90()),
+        Brick::NonUnit(two_by_two)
+    ];
+    for &brick in other_bricks {
+        if brick.length() > 0 && brick.width() > 0 && brick.height() == 1 {
+            plates.push(Brick::NonUnit(brick));
+            plates.push(Brick::NonUnit(brick.rotate_90()));
+        }
     }
+    sort_by_area(&mut plates);
 
-    new_areas
+    plates
 }
 
-// ====================
-// PRIVATE STRUCTS
-// ====================
+fn footprint_layer<U: UnitBrick, B: NonUnitBrick<U>>(footprint: &Footprint, offset: u32, one_by_one: U, two_by_one: B, two_by_two: B,
+                                                     even_by_two_bricks: &[Brick<U, B>], above: &[FilledArea<U, B>]) -> Vec<FilledArea<U, B>> {
+    let length = footprint.length();
+    let width = footprint.width();
+    let blocks = |size: u32| (0u32..)
+        .map(move |index| ((2 * index).saturating_sub(offset), (2 * index + 2 - offset).min(size)))
+        .take_while(move |&(min, _)| min < size);
+
+    let mut areas = Vec::new();
+
+    // The empty block past the east edge ends the last run of whole blocks in each row
+    for (min_w, max_w) in blocks(width) {
+        let mut run_min_l = None;
+
+        for (min_l, max_l) in blocks(length).chain(iter::once((length, length))) {
+            let is_whole = max_l - min_l == 2 && max_w - min_w == 2
+                && (min_l..max_l).all(|l| (min_w..max_w).all(|w| footprint.contains(l, w)));
+
+            match (is_whole, run_min_l) {
+                (true, None) => run_min_l = Some(min_l),
+                (false, Some(run_l)) => {
+                    areas.append(&mut fill(run_l, min_w, min_l - run_l, 2, 0, even_by_two_bricks));
+                    run_min_l = None;
+                },
+                _ => {}
+            }
+        }
+    }
+
+    let covered: BTreeSet<(u32, u32)> = areas.iter().flat_map(|area| area.cells()).collect();
+    let free = (0..width).flat_map(|w| (0..length).map(move |l| (l, w)))
+        .filter(|&(l, w)| footprint.contains(l, w) && !covered.contains(&(l, w)))
+        .collect();
+    let mut outline_areas = cover_studs(free, &areas, above, one_by_one, two_by_one, two_by_two);
+    areas.append(&mut outline_areas);
+
+    areas
+}
+
+/* Plates that join bricks in the other layer which are not joined yet go first. Studs with the
+   fewest free neighbors have the fewest plates to choose from, so their plates are placed before
+   the others can take their neighbors. */
+fn cover_studs<U: UnitBrick, B: NonUnitBrick<U>>(free: BTreeSet<(u32, u32)>, placed: &[FilledArea<U, B>], other: &[FilledArea<U, B>],
+                                                 one_by_one: U, two_by_one: B, two_by_two: B) -> Vec<FilledArea<U, B>> {
+
+    // Number the bricks in the other layer so the bricks in this layer can tell which of them they join
+    let mut other_ids = BTreeMap::new();
+    for (id, area) in other.iter().flat_map(|area| area.bricks()).enumerate() {
+        for cell in area.cells() {
+            other_ids.insert(cell, id);
+        }
+    }
+    let mut groups = Groups::new(other.iter().map(|area| area.brick_count() as usize).sum());
+    for area in placed.iter().flat_map(|area| area.bricks()) {
+        groups.join_all(area.cells().filter_map(|cell| other_ids.get(&cell).copied()));
+    }
+
+    cover_free_studs(free, &other_ids, groups, one_by_one, two_by_one, two_by_two)
+}
+
+// The groups hold the bricks in the other layer, numbered as in other_ids, that are already joined
+fn cover_free_studs<U: UnitBrick, B: NonUnitBrick<U>>(free: BTreeSet<(u32, u32)>, other_ids: &StudIds, groups: Groups,
+                                                      one_by_one: U, two_by_one: B, two_by_two: B) -> Vec<FilledArea<U, B>> {
+    let mut groups = groups;
+
+    let free_neighbors = |(l, w): (u32, u32)| [(l.wrapping_sub(1), w), (l + 1, w), (l, w.wrapping_sub(1)), (l, w + 1)].into_iter()
+        .filter(|cell| free.contains(cell))
+        .count();
+
+    let small_plates = [Brick::NonUnit(two_by_two), Brick::NonUnit(two_by_one), Brick::NonUnit(two_by_one.rotate_90())];
+    let mut candidates: Vec<(usize, FilledArea<U, B>)> = small_plates.iter()
+        .flat_map(|&brick| free.iter().map(move |&(l, w)|
+            FilledArea { brick, l, w, length: brick.length() as u32, width: brick.width() as u32 }
+        ))
+        .filter(|area| area.cells().all(|cell| free.contains(&cell)))
+        .map(|area| (area.cells().map(free_neighbors).min().unwrap_or(0), area))
+        .collect();
+    candidates.sort_by_key(|&(neighbors, _)| neighbors);
+
+    let mut free = free;
+    let mut areas = Vec::new();
+
+    for must_join in [true, false] {
+        for (_, area) in &candidates {
+            if !area.cells().all(|cell| free.contains(&cell)) {
+                continue;
+            }
+
+            let ids: Vec<usize> = area.cells().filter_map(|cell| other_ids.get(&cell).copied()).collect();
+            if must_join && !ids.iter().any(|&id| !groups.is_joined(ids[0], id)) {
+                continue;
+            }
+
+            groups.join_all(ids.into_iter());
+            for cell in area.cells() {
+                free.remove(&cell);
+            }
+            areas.push(*area);
+        }
+    }
+
+    areas.extend(free.into_iter().map(|(l, w)| FilledArea { brick: Brick::Unit(one_by_one), l, w, length: 1, width: 1 }));
+
+    areas
+}
+
+/* Plates along the outline can leave parts of the base that only meet at a narrow neck. Put a
+   plate across the neck in whichever of the movable layers joins the parts and cover the rest of
+   the bricks it overlaps again, until no plate joins any more parts. Only studs inside the
+   footprint are covered again, but the other layer may leave some of them empty.
+
+   Each plate only changes the bricks around it, so the parts are kept up to date from one plate
+   to the next instead of being found again. A plate is only kept when the new bricks join every
+   brick in the other layer that the old bricks joined, so the parts never have to split. */
+fn join_parts<U: UnitBrick, B: NonUnitBrick<U>>(footprint: &Footprint, layers: [Vec<FilledArea<U, B>>; 2], movable: Range<usize>,
+                                                one_by_one: U, two_by_one: B, two_by_two: B) -> [Vec<FilledArea<U, B>>; 2] {
+
+    // Bricks are numbered in the order they are placed, and replaced bricks leave an empty slot
+    let placed: Vec<(usize, FilledArea<U, B>)> = layers.iter().enumerate()
+        .flat_map(|(layer, areas)| areas.iter().flat_map(|area| area.bricks()).map(move |brick| (layer, brick)))
+        .collect();
+    let mut parts: [StudIds; 2] = [BTreeMap::new(), BTreeMap::new()];
+    for (id, (layer, brick)) in placed.iter().enumerate() {
+        for cell in brick.cells() {
+            parts[*layer].insert(cell, id);
+        }
+    }
+    let mut groups = Groups::new(placed.len());
+    let mut bricks: Vec<Option<(usize, FilledArea<U, B>)>> = placed.into_iter().map(Some).collect();
+    for (cell, &id) in &parts[0] {
+        if let Some(&other_id) = parts[1].get(cell) {
+            groups.join(id, other_id);
+        }
+    }
+
+    let cells: Vec<(u32, u32)> = (0..footprint.width())
+        .flat_map(|w| (0..footprint.length()).map(move |l| (l, w)))
+        .filter(|&(l, w)| footprint.contains(l, w))
+        .collect();
+
+    let mut joined_any = true;
+    while joined_any {
+        joined_any = false;
+
+        for &(l, w) in &cells {
+            for (neighbor, brick) in [((l + 1, w), two_by_one), ((l, w + 1), two_by_one.rotate_90())] {
+                if !footprint.contains(neighbor.0, neighbor.1) {
+                    continue;
+                }
+
+                for layer in movable.clone() {
+                    match (parts[1 - layer].get(&(l, w)), parts[1 - layer].get(&neighbor)) {
+                        (Some(&id), Some(&neighbor_id)) if !groups.is_joined(id, neighbor_id) => {},
+                        _ => continue
+                    }
+
+                    let plate = FilledArea { brick: Brick::NonUnit(brick), l, w, length: brick.length() as u32, width: brick.width() as u32 };
+                    if let Some(new_bricks) = join_across(&parts, &mut groups, &bricks, layer, plate, one_by_one, two_by_one, two_by_two) {
+                        let replaced: BTreeSet<usize> = plate.cells().filter_map(|cell| parts[layer].get(&cell).copied()).collect();
+                        for id in replaced {
+                            if let Some((_, old_brick)) = bricks[id].take() {
+                                for cell in old_brick.cells() {
+                                    parts[layer].remove(&cell);
+                                }
+                            }
+                        }
+
+                        for new_brick in new_bricks {
+                            let id = groups.add();
+                            bricks.push(Some((layer, new_brick)));
+                            for cell in new_brick.cells() {
+                                parts[layer].insert(cell, id);
+                                if let Some(&other_id) = parts[1 - layer].get(&cell) {
+                                    groups.join(other_id, id);
+                                }
+                            }
+                        }
+
+                        joined_any = true;
+                    }
+                }
+            }
+        }
+    }
+
+    let mut new_layers = [Vec::new(), Vec::new()];
+    for (layer, brick) in bricks.into_iter().flatten() {
+        new_layers[layer].push(brick);
+    }
+
+    new_layers
+}
+
+/* Covers the bricks in the layer that the plate overlaps again and returns the plate and the new
+   bricks if they join fewer parts than before, or None if the parts would stay the same or could
+   split. Only the bricks under the replaced bricks are looked at. */
+#[allow(clippy::too_many_arguments)]
+fn join_across<U: UnitBrick, B: NonUnitBrick<U>>(parts: &[StudIds; 2], groups: &mut Groups, bricks: &[Option<(usize, FilledArea<U, B>)>],
+                                                 layer: usize, plate: FilledArea<U, B>, one_by_one: U, two_by_one: B,
+                                                 two_by_two: B) -> Option<Vec<FilledArea<U, B>>> {
+    let replaced: BTreeSet<usize> = plate.cells().filter_map(|cell| parts[layer].get(&cell).copied()).collect();
+    let replaced_bricks: Vec<FilledArea<U, B>> = replaced.iter().filter_map(|&id| bricks[id].map(|(_, brick)| brick)).collect();
+
+    let free: BTreeSet<(u32, u32)> = replaced_bricks.iter()
+        .flat_map(|brick| brick.cells())
+        .filter(|&cell| !plate.cells().any(|plate_cell| plate_cell == cell))
+        .collect();
+
+    // Bricks in the other layer under the changed studs, numbered from zero
+    let mut below_ids: Vec<usize> = plate.cells().chain(free.iter().copied())
+        .filter_map(|cell| parts[1 - layer].get(&cell).copied())
+        .collect();
+    below_ids.sort_unstable();
+    below_ids.dedup();
+    let local_ids: StudIds = plate.cells().chain(free.iter().copied())
+        .filter_map(|cell| parts[1 - layer].get(&cell).map(|id| (cell, below_ids.binary_search(id).unwrap())))
+        .collect();
+
+    // Bricks in this layer that stay in place can join the bricks under the changed studs too
+    let mut kept_joins = Groups::new(below_ids.len());
+    let mut kept_below: BTreeMap<usize, usize> = BTreeMap::new();
+    for (local_id, &id) in below_ids.iter().enumerate() {
+        let below_brick = match bricks[id] {
+            Some((_, below_brick)) => below_brick,
+            None => continue
+        };
+
+        for cell in below_brick.cells() {
+            if let Some(&kept_id) = parts[layer].get(&cell).filter(|kept_id| !replaced.contains(kept_id)) {
+                match kept_below.get(&kept_id) {
+                    Some(&other_local_id) => kept_joins.join(other_local_id, local_id),
+                    None => {
+                        kept_below.insert(kept_id, local_id);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut new_joins = kept_joins.clone();
+    new_joins.join_all(plate.cells().filter_map(|cell| local_ids.get(&cell).copied()));
+    let mut new_bricks = vec![plate];
+    new_bricks.append(&mut cover_free_studs(free, &local_ids, new_joins, one_by_one, two_by_one, two_by_two));
+
+    let mut new_joins = kept_joins;
+    for brick in &new_bricks {
+        new_joins.join_all(brick.cells().filter_map(|cell| local_ids.get(&cell).copied()));
+    }
+
+    // Every pair of bricks the replaced bricks joined must still be joined
+    for brick in &replaced_bricks {
+        let ids: Vec<usize> = brick.cells().filter_map(|cell| local_ids.get(&cell).copied()).collect();
+        if ids.iter().any(|&id| !new_joins.is_joined(ids[0], id)) {
+            return None;
+        }
+    }
+
+    // Bricks with nothing under them are parts on their own
+    let lone_bricks = |bricks: &[FilledArea<U, B>]| bricks.iter()
+        .filter(|brick| !brick.cells().any(|cell| local_ids.contains_key(&cell)))
+        .count();
+
+    let mut roots: Vec<usize> = below_ids.iter().map(|&id| groups.root(id)).collect();
+    roots.sort_unstable();
+    roots.dedup();
+    let root_indices: Vec<usize> = below_ids.iter().map(|&id| roots.binary_search(&groups.root(id)).unwrap()).collect();
+    let mut root_joins = Groups::new(roots.len());
+    for local_id in 0..below_ids.len() {
+        root_joins.join(root_indices[new_joins.root(local_id)], root_indices[local_id]);
+    }
+
+    let old_part_count = roots.len() + lone_bricks(&replaced_bricks);
+    let new_part_count = root_joins.count() + lone_bricks(&new_bricks);
+    match new_part_count < old_part_count {
+        true => Some(new_bricks),
+        false => None
+    }
+}
+
+fn unjoined_stud<U: UnitBrick, B: NonUnitBrick<U>>(footprint: &Footprint, layers: &[Vec<FilledArea<U, B>>; 2]) -> Option<(u32, u32)> {
+    let (parts, mut groups) = layer_groups(layers);
+    let mut visited = BTreeSet::new();
+
+    for start in (0..footprint.width()).flat_map(|w| (0..footprint.length()).map(move |l| (l, w))) {
+        if !footprint.contains(start.0, start.1) || !visited.insert(start) {
+            continue;
+        }
+
+        let part = groups.root(parts[1][&start]);
+        let mut stack = vec![start];
+
+        while let Some((l, w)) = stack.pop() {
+            if groups.root(parts[1][&(l, w)]) != part {
+                return Some((l, w));
+            }
+
+            for neighbor in [(l.wrapping_sub(1), w), (l + 1, w), (l, w.wrapping_sub(1)), (l, w + 1)] {
+                if footprint.contains(neighbor.0, neighbor.1) && visited.insert(neighbor) {
+                    stack.push(neighbor);
+                }
+            }
+        }
+    }
+
+    None
+}
+
+// Bricks in the two layers are numbered one after the other, and bricks that share a stud are joined
+fn layer_groups<U: UnitBrick, B: NonUnitBrick<U>>(layers: &[Vec<FilledArea<U, B>>; 2]) -> ([StudIds; 2], Groups) {
+    let mut parts = [BTreeMap::new(), BTreeMap::new()];
+    for (id, (layer, area)) in layers.iter().enumerate()
+        .flat_map(|(layer, areas)| areas.iter().map(move |area| (layer, area)))
+        .enumerate() {
+        for cell in area.cells() {
+            parts[layer].insert(cell, id);
+        }
+    }
+
+    let mut groups = Groups::new(layers[0].len() + layers[1].len());
+    for (cell, &id) in &parts[0] {
+        if let Some(&other_id) = parts[1].get(cell) {
+            groups.join(id, other_id);
+        }
+    }
+
+    (parts, groups)
+}
+
+fn segments(total: u32, size: u32) -> Vec<(u32, u32)> {
+    (0..total).step_by(size.max(1) as usize)
+        .map(|start| (start, size.min(total - start)))
+        .collect()
+}
+
+fn is_even(n: u32) -> bool {
+    n.is_multiple_of(2)
+}
+
+fn make_even(n: u32) -> u32 {
+    n & !1
+}
+
+fn sub_at_most(n: u32, amount: u32) -> u32 {
+    n - n.min(amount)
+}
+
+fn sort_by_area<U: UnitBrick, B: NonUnitBrick<U>>(bricks: &mut [Brick<U, B>]) {
+    bricks.sort_by(|brick1, brick2| {
+        let area1= brick1.length() as u16 * brick1.width() as u16;
+        let area2 = brick2.length() as u16 * brick2.width() as u16;
+
+        // Sort in descending order
+        area2.cmp(&area1)
+
+    });
+}
+
+fn fill<U: UnitBrick, B: NonUnitBrick<U>>(min_l: u32, min_w: u32, length: u32, width: u32, min_index: usize, bricks: &[Brick<U, B>]) -> Vec<FilledArea<U, B>> {
+    let mut remaining_length = length;
+    let mut remaining_width = width;
+
+    let mut new_areas = Vec::new();
+
+    let mut filled_length = 0;
+    let mut filled_width = 0;
+
+    let mut index = min_index;
+    while index < bricks.len() {
+        let brick = bricks[index];
+        if brick.length() as u32 <= remaining_length && brick.width() as u32 <= remaining_width {
+            remaining_length %= brick.length() as u32;
+            remaining_width %= brick.width() as u32;
+
+            filled_length = length - remaining_length;
+            filled_width = width - remaining_width;
+            new_areas.push(FilledArea {
+                brick,
+                l: min_l,
+                w: min_w,
+                length: filled_length,
+                width: filled_width
+            });
+
+            break;
+        }
+
+        index += 1;
+    }
+
+    // Fill following regions with next largest brick
+    index += 1;
+
+    if filled_length > 0 && remaining_width > 0 {
+        let mut areas_below = fill(
+            min_l,
+            min_w + filled_width,
+            filled_length,
+            remaining_width,
+            index,
+            bricks
+        );
+        new_areas.append(&mut areas_below);
+    }
+
+    if remaining_length > 0 && width > 0 {
+        let mut areas_right = fill(
+            min_l + filled_length,
+            min_w,
+            remaining_length,
+            width,
+            index,
+            bricks
+        );
+        new_areas.append(&mut areas_right);
+    }
+
+    new_areas
+}
+
+// ====================
+// PRIVATE STRUCTS
+// ====================
 
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 struct FilledArea<U, B> {
@@ -330,6 +1246,67 @@ struct FilledArea<U, B> {
 }
 
 impl<U: UnitBrick, B: NonUnitBrick<U>> FilledArea<U, B> {
+    fn brick_count(&self) -> u32 {
+        (self.length / self.brick.length() as u32) * (self.width / self.brick.width() as u32)
+    }
+
+    fn bricks(&self) -> impl Iterator<Item=FilledArea<U, B>> + '_ {
+        let brick_length = self.brick.length() as u32;
+        let brick_width = self.brick.width() as u32;
+
+        (self.l..(self.l + self.length)).step_by(brick_length as usize).flat_map(move |l|
+            (self.w..(self.w + self.width)).step_by(brick_width as usize).map(move |w|
+                FilledArea { brick: self.brick, l, w, length: brick_length, width: brick_width }
+            )
+        )
+    }
+
+    fn cells(&self) -> impl Iterator<Item=(u32, u32)> + '_ {
+        (self.l..(self.l + self.length)).flat_map(move |l| (self.w..(self.w + self.width)).map(move |w| (l, w)))
+    }
+
+    fn overlaps(&self, other: &FilledArea<U, B>) -> bool {
+        self.l < other.l + other.length && other.l < self.l + self.length
+            && self.w < other.w + other.width && other.w < self.w + self.width
+    }
+
+    // Bricks under the hole are replaced by plates filling the rest of their own footprint, so nothing outside the hole moves
+    fn carve(&self, hole: &FilledArea<U, B>, plates: &[Brick<U, B>]) -> Vec<FilledArea<U, B>> {
+        if !self.overlaps(hole) {
+            return vec![*self];
+        }
+
+        let mut areas = Vec::new();
+
+        for brick_area in self.bricks() {
+            if !brick_area.overlaps(hole) {
+                areas.push(brick_area);
+                continue;
+            }
+
+            let FilledArea { l, w, length, width, .. } = brick_area;
+            let hole_max_l = hole.l + hole.length;
+            let hole_max_w = hole.w + hole.width;
+            let middle_min_w = w.max(hole.w);
+            let middle_max_w = (w + width).min(hole_max_w);
+
+            let remaining = [
+                (l, w, length, sub_at_most(hole.w, w)),
+                (l, hole_max_w, length, sub_at_most(w + width, hole_max_w)),
+                (l, middle_min_w, sub_at_most(hole.l, l), middle_max_w - middle_min_w),
+                (hole_max_l, middle_min_w, sub_at_most(l + length, hole_max_l), middle_max_w - middle_min_w)
+            ];
+
+            for (min_l, min_w, length, width) in remaining {
+                if length > 0 && width > 0 {
+                    areas.append(&mut fill(min_l, min_w, length, width, 0, plates));
+                }
+            }
+        }
+
+        areas
+    }
+
     fn build_supports(&self, bricks: &[Brick<U, B>], mosaic_length: u32, mosaic_width: u32) -> Vec<FilledArea<U, B>> {
         let (length_two_bricks, width_two_bricks) = FilledArea::<U, B>::filter_bricks(bricks);
 
@@ -520,11 +1497,61 @@ impl<U: UnitBrick, B: NonUnitBrick<U>> FilledArea<U, B> {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "default"))]
 mod tests {
     use std::collections::BTreeSet;
-    use crate::{Base, BaseError, NonUnitBrick};
-    use crate::tests::{EIGHT_BY_EIGHT_PLATE, FOUR_BY_FOUR_BY_TWO_BRICK, FOUR_BY_FOUR_PLATE, FOUR_BY_THREE_PLATE, FOUR_BY_TWO_PLATE, ONE_BY_TWO_PLATE, TestBrick, TestColor, THREE_BY_ONE_PLATE, THREE_BY_THREE_PLATE, THREE_BY_TWO_PLATE, TWO_BY_ONE_BY_TWO_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_BY_TWO_BRICK, TWO_BY_TWO_PLATE, UNIT_BRICK, ZERO_BY_TWO_PLATE};
+    use crate::base::{is_edge_seam, FilledArea};
+    use crate::{Base, BaseError, BaseWarning, BillOfMaterials, Brick, BridgeLayer, ConnectivityGraph, Footprint, Hangers, Mosaic, NonUnitBrick, PlacedBrick, Seam, SeamDirection, ValidationIssue};
+    use crate::tests::{COLOR_1, COLOR_2, COLOR_3, COLOR_4, make_single_color_img, make_test_img, EIGHT_BY_EIGHT_PLATE, FOUR_BY_FOUR_BY_TWO_BRICK, FOUR_BY_FOUR_PLATE, FOUR_BY_THREE_PLATE, FOUR_BY_TWO_PLATE, ONE_BY_TWO_PLATE, SIX_BY_ONE_PLATE, TestBrick, TestColor, THREE_BY_ONE_PLATE, THREE_BY_THREE_PLATE, THREE_BY_TWO_PLATE, TWO_BY_ONE_BY_TWO_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_BY_TWO_BRICK, TWO_BY_TWO_PLATE, UNIT_BRICK, ZERO_BY_TWO_PLATE};
+
+    fn layer_counts(base: &Base<u8, TestBrick, TestColor>, h: u32) -> Vec<Vec<u32>> {
+        let mut counts = vec![vec![0; base.length() as usize]; base.width() as usize];
+
+        for placed_brick in base.iter().filter(|placed_brick| placed_brick.h == h) {
+            for l in placed_brick.l..(placed_brick.l + placed_brick.brick.length() as u32) {
+                for w in placed_brick.w..(placed_brick.w + placed_brick.brick.width() as u32) {
+                    counts[w as usize][l as usize] += 1;
+                }
+            }
+        }
+
+        counts
+    }
+
+    fn assert_no_overlaps(base: &Base<u8, TestBrick, TestColor>) {
+        let mut cells = BTreeSet::new();
+
+        for placed_brick in base.iter() {
+            for l in placed_brick.l..(placed_brick.l + placed_brick.brick.length() as u32) {
+                for w in placed_brick.w..(placed_brick.w + placed_brick.brick.width() as u32) {
+                    for h in placed_brick.h..(placed_brick.h + placed_brick.brick.height() as u32) {
+                        assert!(cells.insert((l, w, h)), "overlap at ({}, {}, {})", l, w, h);
+                    }
+                }
+            }
+        }
+    }
+
+    fn assert_follows_footprint(base: &Base<u8, TestBrick, TestColor>, footprint: &Footprint) {
+        assert_no_overlaps(base);
+        assert_eq!((footprint.length(), footprint.width()), (base.length(), base.width()));
+
+        for h in 0..2 {
+            let counts = layer_counts(base, h);
+            for l in 0..footprint.length() {
+                for w in 0..footprint.width() {
+                    assert_eq!(footprint.contains(l, w) as u32, counts[w as usize][l as usize]);
+                }
+            }
+        }
+    }
+
+    fn is_grid_seam(seam: &Seam, grid_ls: &[u32], grid_ws: &[u32]) -> bool {
+        match seam.direction {
+            SeamDirection::East => grid_ls.contains(&(seam.l + 1)),
+            SeamDirection::South => grid_ws.contains(&(seam.w + 1))
+        }
+    }
 
     fn assert_valid_base<const L: usize, const W: usize>(base: &Base<u8, TestBrick, TestColor>,
                                                          expected_connections: &[&[(u32, u32)]],
@@ -2431,4 +3458,728 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn test_baseplates_bridged_above() {
+        let base = Base::with_baseplates(
+            20,
+            13,
+            TestColor::default(),
+            &[EIGHT_BY_EIGHT_PLATE],
+            BridgeLayer::Above,
+            UNIT_BRICK,
+            TWO_BY_ONE_PLATE,
+            TWO_BY_TWO_PLATE,
+            &[]
+        ).unwrap();
+
+        assert_eq!(2, base.baseplate_count());
+        assert_eq!(23, base.joiner_count());
+        assert_eq!(2, base.iter().filter(|brick| brick.brick == crate::Brick::NonUnit(EIGHT_BY_EIGHT_PLATE)).count());
+        assert_eq!(vec![vec![1; 20]; 13], layer_counts(&base, 0));
+        assert_eq!(vec![vec![1; 20]; 13], layer_counts(&base, 1));
+
+        let seams = ConnectivityGraph::new(base.iter()).unbridged_seams(0);
+        assert!(!seams.iter().any(|seam| is_grid_seam(seam, &[8, 16], &[8])));
+    }
+
+    #[test]
+    fn test_baseplates_bridged_below() {
+        let base = Base::with_baseplates(
+            20,
+            13,
+            TestColor::default(),
+            &[EIGHT_BY_EIGHT_PLATE],
+            BridgeLayer::Below,
+            UNIT_BRICK,
+            TWO_BY_ONE_PLATE,
+            TWO_BY_TWO_PLATE,
+            &[]
+        ).unwrap();
+
+        assert_eq!(2, base.baseplate_count());
+        assert_eq!(23, base.joiner_count());
+        assert_eq!(vec![vec![1; 20]; 13], layer_counts(&base, 1));
+
+        // Only the joiners and the cells filled with plates need a layer underneath
+        let support_counts = layer_counts(&base, 0);
+        assert_eq!(0, support_counts[3][3]);
+        assert_eq!(1, support_counts[3][7]);
+        assert_eq!(1, support_counts[11][3]);
+        assert!(support_counts.iter().flatten().all(|&count| count <= 1));
+
+        let seams = ConnectivityGraph::new(base.iter()).unbridged_seams(1);
+        assert!(!seams.iter().any(|seam| is_grid_seam(seam, &[8, 16], &[8])));
+    }
+
+    #[test]
+    fn test_baseplates_use_largest_fitting_size() {
+        let base = Base::with_baseplates(
+            8,
+            20,
+            TestColor::default(),
+            &[FOUR_BY_FOUR_PLATE, EIGHT_BY_EIGHT_PLATE],
+            BridgeLayer::Above,
+            UNIT_BRICK,
+            TWO_BY_ONE_PLATE,
+            TWO_BY_TWO_PLATE,
+            &[]
+        ).unwrap();
+
+        assert_eq!(2, base.baseplate_count());
+        assert_eq!(vec![vec![1; 8]; 20], layer_counts(&base, 0));
+        assert_eq!(vec![vec![1; 8]; 20], layer_counts(&base, 1));
+    }
+
+    #[test]
+    fn test_baseplates_larger_than_mosaic() {
+        let base = Base::with_baseplates(
+            6,
+            5,
+            TestColor::default(),
+            &[EIGHT_BY_EIGHT_PLATE],
+            BridgeLayer::Below,
+            UNIT_BRICK,
+            TWO_BY_ONE_PLATE,
+            TWO_BY_TWO_PLATE,
+            &[]
+        ).unwrap();
+
+        assert_eq!(0, base.baseplate_count());
+        assert_eq!(0, base.joiner_count());
+        assert_eq!(vec![vec![1; 6]; 5], layer_counts(&base, 1));
+    }
+
+    #[test]
+    fn test_baseplate_too_small() {
+        let base = Base::with_baseplates(
+            20,
+            20,
+            TestColor::default(),
+            &[EIGHT_BY_EIGHT_PLATE, THREE_BY_THREE_PLATE],
+            BridgeLayer::Below,
+            UNIT_BRICK,
+            TWO_BY_ONE_PLATE,
+            TWO_BY_TWO_PLATE,
+            &[]
+        );
+
+        assert_eq!(Err(BaseError::NotABaseplate(THREE_BY_THREE_PLATE)), base);
+    }
+
+    #[test]
+    fn test_single_layer_base() {
+        let base = Base::with_height(
+            5,
+            3,
+            1,
+            TestColor::default(),
+            UNIT_BRICK,
+            TWO_BY_ONE_PLATE,
+            TWO_BY_TWO_PLATE,
+            &[]
+        ).unwrap();
+
+        assert_eq!(1, base.height());
+        assert_eq!(vec![vec![1; 5]; 3], layer_counts(&base, 0));
+    }
+
+    #[test]
+    fn test_two_layer_base_matches_new() {
+        let base = Base::with_height(
+            9,
+            7,
+            2,
+            TestColor::default(),
+            UNIT_BRICK,
+            TWO_BY_ONE_PLATE,
+            TWO_BY_TWO_PLATE,
+            &[FOUR_BY_TWO_PLATE]
+        ).unwrap();
+
+        assert_eq!(Base::new(9, 7, TestColor::default(), UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, &[FOUR_BY_TWO_PLATE]).unwrap(), base);
+    }
+
+    #[test]
+    fn test_multi_layer_bases_have_no_through_seams() {
+        for height in 3..=5 {
+            for length in 1..=13 {
+                for width in 1..=13 {
+                    let base = Base::with_height(
+                        length,
+                        width,
+                        height,
+                        TestColor::default(),
+                        UNIT_BRICK,
+                        TWO_BY_ONE_PLATE,
+                        TWO_BY_TWO_PLATE,
+                        &[FOUR_BY_TWO_PLATE, FOUR_BY_FOUR_PLATE, THREE_BY_ONE_PLATE]
+                    ).unwrap();
+
+                    assert_eq!(height, base.height());
+                    assert!(base.through_seams().is_empty());
+
+                    // Layers that are not supports cover the whole base
+                    for h in (0..height).filter(|h| (height - 1 - h) % 2 == 0) {
+                        assert_eq!(vec![vec![1; length as usize]; width as usize], layer_counts(&base, h));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_multi_layer_base_keeps_top_layers() {
+        let two_layers = Base::new(10, 6, TestColor::default(), UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, &[]).unwrap();
+        let four_layers = Base::with_height(10, 6, 4, TestColor::default(), UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, &[]).unwrap();
+
+        let top_layers: Vec<_> = four_layers.iter()
+            .filter(|brick| brick.h >= 2)
+            .map(|brick| crate::PlacedBrick { h: brick.h - 2, ..brick })
+            .collect();
+        assert_eq!(two_layers.iter().collect::<Vec<_>>(), top_layers);
+    }
+
+    #[test]
+    fn test_two_layer_base_can_have_through_seams() {
+        let base = Base::new(1, 4, TestColor::default(), UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, &[]).unwrap();
+
+        assert_eq!(vec![Seam { l: 0, w: 1, h: 0, direction: SeamDirection::South }], base.through_seams());
+    }
+
+    #[test]
+    fn test_two_layer_bases_only_have_edge_seams() {
+        for length in 1..=13 {
+            for width in 1..=13 {
+                let base = Base::new(
+                    length,
+                    width,
+                    TestColor::default(),
+                    UNIT_BRICK,
+                    TWO_BY_ONE_PLATE,
+                    TWO_BY_TWO_PLATE,
+                    &[FOUR_BY_TWO_PLATE, FOUR_BY_FOUR_PLATE, THREE_BY_ONE_PLATE]
+                ).unwrap();
+
+                assert!(base.through_seams().iter().all(|seam| is_edge_seam(seam, length, width)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_two_layer_base_warns_about_inner_seams() {
+        let base = Base::new(
+            6,
+            3,
+            TestColor::default(),
+            UNIT_BRICK,
+            TWO_BY_ONE_PLATE,
+            TWO_BY_TWO_PLATE,
+            &[SIX_BY_ONE_PLATE]
+        ).unwrap();
+        assert_eq!(&[BaseWarning::SeamThroughAllLayers(1, 1)], base.warnings());
+
+        let base = Base::with_height(
+            6,
+            3,
+            2,
+            TestColor::default(),
+            UNIT_BRICK,
+            TWO_BY_ONE_PLATE,
+            TWO_BY_TWO_PLATE,
+            &[SIX_BY_ONE_PLATE]
+        );
+        assert_eq!(Err(BaseError::SeamThroughAllLayers(1, 1)), base);
+    }
+
+    #[test]
+    fn test_multi_layer_bases_with_hangers_have_no_through_seams() {
+        let hangers = Hangers { brick: TWO_BY_ONE_BY_TWO_BRICK, from_top: 1, spacing: 4 };
+        for height in 3..=4 {
+            for length in 2..=13 {
+                for width in 2..=13 {
+                    let base = Base::with_hangers(
+                        length,
+                        width,
+                        height,
+                        TestColor::default(),
+                        UNIT_BRICK,
+                        TWO_BY_ONE_PLATE,
+                        TWO_BY_TWO_PLATE,
+                        &[FOUR_BY_TWO_PLATE],
+                        hangers
+                    );
+
+                    match base {
+                        Ok(base) => assert!(base.through_seams().is_empty()),
+                        Err(error) => assert!(matches!(error, BaseError::SeamThroughAllLayers(_, _) | BaseError::HangerDoesNotFit(_)))
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_zero_height_base() {
+        let base = Base::with_height(
+            4,
+            4,
+            0,
+            TestColor::default(),
+            UNIT_BRICK,
+            TWO_BY_ONE_PLATE,
+            TWO_BY_TWO_PLATE,
+            &[]
+        );
+
+        assert_eq!(Err(BaseError::ZeroHeight), base);
+    }
+
+    #[test]
+    fn test_hangers_stick_out_below_base() {
+        let hangers = Hangers { brick: TWO_BY_ONE_BY_TWO_BRICK, from_top: 1, spacing: 8 };
+        let base = Base::with_hangers(
+            16,
+            10,
+            2,
+            TestColor::default(),
+            UNIT_BRICK,
+            TWO_BY_ONE_PLATE,
+            TWO_BY_TWO_PLATE,
+            &[FOUR_BY_TWO_PLATE],
+            hangers
+        ).unwrap();
+
+        assert_eq!(3, base.height());
+        assert_no_overlaps(&base);
+        assert_eq!(vec![vec![1; 16]; 10], layer_counts(&base, 2));
+        assert_eq!(1, ConnectivityGraph::new(base.iter()).components().len());
+
+        let hanger_positions: Vec<(u32, u32, u32)> = base.iter()
+            .filter(|brick| brick.brick == Brick::NonUnit(TWO_BY_ONE_BY_TWO_BRICK))
+            .map(|brick| (brick.l, brick.w, brick.h))
+            .collect();
+        assert_eq!(vec![(3, 1, 0), (11, 1, 0)], hanger_positions);
+
+        let bill = BillOfMaterials::new(base.iter());
+        assert_eq!(2, bill.count(Brick::NonUnit(TWO_BY_ONE_BY_TWO_BRICK), TestColor::default()));
+    }
+
+    #[test]
+    fn test_single_hanger_in_middle() {
+        let hangers = Hangers { brick: ONE_BY_TWO_PLATE, from_top: 2, spacing: 0 };
+        let base = Base::with_hangers(
+            11,
+            8,
+            2,
+            TestColor::default(),
+            UNIT_BRICK,
+            TWO_BY_ONE_PLATE,
+            TWO_BY_TWO_PLATE,
+            &[],
+            hangers
+        ).unwrap();
+        let without_hangers = Base::new(11, 8, TestColor::default(), UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, &[]).unwrap();
+
+        assert_eq!(2, base.height());
+        assert_no_overlaps(&base);
+        assert_eq!(1, ConnectivityGraph::new(base.iter()).components().len());
+        assert!(base.iter().any(|brick| brick.l == 5 && brick.w == 2 && brick.h == 0 && brick.brick == Brick::NonUnit(ONE_BY_TWO_PLATE)));
+
+        // Only bricks under the hanger change, and the plates replacing them cover the same studs
+        assert_eq!(layer_counts(&without_hangers, 1), layer_counts(&base, 1));
+        let support_counts = layer_counts(&base, 0);
+        let old_support_counts = layer_counts(&without_hangers, 0);
+        assert_eq!(1, support_counts[2][5]);
+        assert_eq!(1, support_counts[3][5]);
+        assert_eq!(old_support_counts, support_counts);
+    }
+
+    #[test]
+    fn test_hangers_in_multi_layer_base() {
+        let hangers = Hangers { brick: TWO_BY_ONE_BY_TWO_BRICK.rotate_90(), from_top: 0, spacing: 4 };
+        let base = Base::with_hangers(
+            13,
+            9,
+            4,
+            TestColor::default(),
+            UNIT_BRICK,
+            TWO_BY_ONE_PLATE,
+            TWO_BY_TWO_PLATE,
+            &[],
+            hangers
+        ).unwrap();
+
+        assert_eq!(5, base.height());
+        assert_no_overlaps(&base);
+        assert!(base.through_seams().is_empty());
+        assert_eq!(1, ConnectivityGraph::new(base.iter()).components().len());
+        assert_eq!(4, base.iter().filter(|brick| brick.h == 0).count());
+    }
+
+    #[test]
+    fn test_hanger_does_not_fit() {
+        let make_base = |hangers| Base::with_hangers(
+            8,
+            6,
+            2,
+            TestColor::default(),
+            UNIT_BRICK,
+            TWO_BY_ONE_PLATE,
+            TWO_BY_TWO_PLATE,
+            &[],
+            hangers
+        );
+
+        assert_eq!(
+            Err(BaseError::HangerDoesNotFit(TWO_BY_TWO_BY_TWO_BRICK)),
+            make_base(Hangers { brick: TWO_BY_TWO_BY_TWO_BRICK, from_top: 5, spacing: 0 })
+        );
+        assert_eq!(
+            Err(BaseError::HangerDoesNotFit(TWO_BY_TWO_BY_TWO_BRICK)),
+            make_base(Hangers { brick: TWO_BY_TWO_BY_TWO_BRICK, from_top: 0, spacing: 1 })
+        );
+        assert_eq!(
+            Err(BaseError::HangerDoesNotFit(ZERO_BY_TWO_PLATE)),
+            make_base(Hangers { brick: ZERO_BY_TWO_PLATE, from_top: 0, spacing: 0 })
+        );
+    }
+
+    #[test]
+    fn test_footprint_base_follows_circle() {
+        for diameter in 1..=20 {
+            let circle = Footprint::circle(diameter);
+            let base = Base::with_footprint(&circle, TestColor::default(), UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, &[FOUR_BY_TWO_PLATE]).unwrap();
+
+            assert_eq!(2, base.height());
+            assert_follows_footprint(&base, &circle);
+            assert_eq!(1, ConnectivityGraph::new(base.iter()).components().len());
+        }
+    }
+
+    #[test]
+    fn test_footprint_base_with_concave_corners() {
+        let heart = Footprint::from_fn(25, 23, |l, w| {
+            let x = (l as f64 + 0.5 - 12.5) / 10f64;
+            let y = (11.5 - w as f64 - 0.5) / 10f64 + 0.2;
+            (x * x + y * y - 1f64).powi(3) - x * x * y.powi(3) <= 0f64
+        });
+        let u_shape = Footprint::from_fn(13, 11, |l, w| !(4..9).contains(&l) || w >= 7);
+
+        for footprint in [heart, u_shape] {
+            let base = Base::with_footprint(&footprint, TestColor::default(), UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, &[FOUR_BY_TWO_PLATE]).unwrap();
+
+            assert_follows_footprint(&base, &footprint);
+            assert_eq!(1, ConnectivityGraph::new(base.iter()).components().len());
+        }
+    }
+
+    #[test]
+    fn test_footprint_base_keeps_islands_apart() {
+        let islands = Footprint::from_fn(12, 5, |l, _| !(5..7).contains(&l));
+        let base = Base::with_footprint(&islands, TestColor::default(), UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, &[]).unwrap();
+
+        assert_follows_footprint(&base, &islands);
+        assert_eq!(2, ConnectivityGraph::new(base.iter()).components().len());
+    }
+
+    #[test]
+    fn test_full_footprint_base() {
+        let footprint = Footprint::from_fn(9, 7, |_, _| true);
+        let base = Base::with_footprint(&footprint, TestColor::default(), UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, &[FOUR_BY_TWO_PLATE]).unwrap();
+
+        assert_follows_footprint(&base, &footprint);
+        assert_eq!(1, ConnectivityGraph::new(base.iter()).components().len());
+        assert!(base.iter().any(|brick| brick.brick == Brick::NonUnit(FOUR_BY_TWO_PLATE)));
+    }
+
+    #[test]
+    fn test_large_footprint_base() {
+        let circle = Footprint::circle(128);
+
+        // Slits leave the strips between them joined only by narrow necks
+        let slits = Footprint::from_fn(128, 128, |l, w| l % 16 != 7 || w % 32 < 4);
+
+        for footprint in [circle, slits] {
+            let base = Base::with_footprint(&footprint, TestColor::default(), UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, &[FOUR_BY_TWO_PLATE]).unwrap();
+
+            assert_follows_footprint(&base, &footprint);
+            assert_eq!(1, ConnectivityGraph::new(base.iter()).components().len());
+        }
+    }
+
+    #[test]
+    fn test_unjoined_footprint() {
+
+        // Two layers of plates cannot hold all four arms of a plus sign to its middle
+        let plus = Footprint::from_fn(3, 3, |l, w| l == 1 || w == 1);
+        let base = Base::with_footprint(&plus, TestColor::default(), UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, &[]);
+
+        assert!(matches!(base, Err(BaseError::UnjoinedFootprint(_, _))));
+    }
+
+    #[test]
+    fn test_recolor_by_layer() {
+        let base = Base::new(10, 6, TestColor::default(), UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, &[FOUR_BY_TWO_PLATE]).unwrap();
+        let recolored = base.clone().recolor(
+            |_, _, h| if h == 0 { COLOR_2 } else { COLOR_1 },
+            UNIT_BRICK,
+            TWO_BY_ONE_PLATE,
+            TWO_BY_TWO_PLATE
+        ).unwrap();
+
+        assert!(base.iter().zip(recolored.iter()).all(|(brick, recolored_brick)| brick == crate::PlacedBrick { color: brick.color, ..recolored_brick }));
+        assert_eq!(base.iter().count(), recolored.iter().count());
+        assert!(recolored.iter().all(|brick| brick.color == if brick.h == 0 { COLOR_2 } else { COLOR_1 }));
+    }
+
+    #[test]
+    fn test_recolor_splits_plates_across_colors() {
+        let color_fn = |l: u32, w: u32, h: u32| match (h, l < 5 && w != 3) {
+            (1, true) => COLOR_1,
+            (1, false) => COLOR_2,
+            _ => COLOR_3
+        };
+        let base = Base::new(10, 6, TestColor::default(), UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, &[FOUR_BY_TWO_PLATE]).unwrap()
+            .recolor(color_fn, UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE)
+            .unwrap();
+
+        assert_no_overlaps(&base);
+        assert_eq!(vec![vec![1; 10]; 6], layer_counts(&base, 1));
+        assert_eq!(1, ConnectivityGraph::new(base.iter()).components().len());
+
+        for brick in base.iter() {
+            for l in brick.l..(brick.l + brick.brick.length() as u32) {
+                for w in brick.w..(brick.w + brick.brick.width() as u32) {
+                    assert_eq!(color_fn(l, w, brick.h), brick.color);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_recolor_keeps_hangers_whole() {
+        let hangers = Hangers { brick: TWO_BY_ONE_BY_TWO_BRICK, from_top: 0, spacing: 0 };
+        let base = Base::with_hangers(8, 6, 2, TestColor::default(), UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, &[], hangers).unwrap()
+            .recolor(|l, _, _| if l < 4 { COLOR_1 } else { COLOR_2 }, UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE)
+            .unwrap();
+
+        let hanger = base.iter().find(|brick| brick.brick == Brick::NonUnit(TWO_BY_ONE_BY_TWO_BRICK)).unwrap();
+        assert_eq!((3, 0, COLOR_1), (hanger.l, hanger.w, hanger.color));
+    }
+
+    #[test]
+    fn test_recolor_with_image() {
+        let (img, palette) = make_test_img();
+        let base = Base::new(6, 5, TestColor::default(), UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, &[]).unwrap()
+            .recolor_with_image(&img, &palette, COLOR_3, UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE)
+            .unwrap();
+
+        assert_no_overlaps(&base);
+        for brick in base.iter() {
+            for l in brick.l..(brick.l + brick.brick.length() as u32) {
+                for w in brick.w..(brick.w + brick.brick.width() as u32) {
+                    let expected = match brick.h == 1 && l < 4 {
+                        true => crate::Palette::nearest(&palette, crate::Image::pixel(&img, l, w)).unwrap(),
+                        false => COLOR_3
+                    };
+                    assert_eq!(expected, brick.color);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_recolor_with_bad_plate() {
+        let base = Base::new(4, 4, TestColor::default(), UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, &[]).unwrap()
+            .recolor(|_, _, _| COLOR_1, UNIT_BRICK, TWO_BY_ONE_PLATE, THREE_BY_TWO_PLATE);
+
+        assert_eq!(Err(BaseError::NotATwoByTwoPlate(THREE_BY_TWO_PLATE)), base);
+    }
+
+    #[test]
+    fn test_warnings_for_ignored_bricks() {
+        let base = Base::with_height(
+            0,
+            4,
+            2,
+            COLOR_1,
+            UNIT_BRICK,
+            TWO_BY_ONE_PLATE,
+            TWO_BY_TWO_PLATE,
+            &[ZERO_BY_TWO_PLATE, TWO_BY_ONE_BY_TWO_BRICK, FOUR_BY_TWO_PLATE]
+        ).unwrap();
+
+        assert_eq!(0, base.iter().count());
+        assert_eq!(&[
+            BaseWarning::ZeroSize,
+            BaseWarning::ZeroSizedBrick(ZERO_BY_TWO_PLATE),
+            BaseWarning::NotAPlate(TWO_BY_ONE_BY_TWO_BRICK)
+        ], base.warnings());
+
+        let base = Base::with_height(4, 4, 2, COLOR_1, UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, &[FOUR_BY_TWO_PLATE]).unwrap();
+        assert!(base.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_warnings_from_every_constructor() {
+        let other_bricks = [TWO_BY_ONE_BY_TWO_BRICK];
+        let expected = [BaseWarning::NotAPlate(TWO_BY_ONE_BY_TWO_BRICK)];
+
+        let base = Base::with_baseplates(20, 13, COLOR_1, &[EIGHT_BY_EIGHT_PLATE], BridgeLayer::Below, UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, &other_bricks).unwrap();
+        assert_eq!(&expected, base.warnings());
+
+        let base = Base::with_footprint(&Footprint::circle(9), COLOR_1, UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, &other_bricks).unwrap();
+        assert_eq!(&expected, base.warnings());
+
+        let hangers = Hangers { brick: TWO_BY_ONE_BY_TWO_BRICK, from_top: 1, spacing: 8 };
+        let base = Base::with_hangers(16, 10, 2, COLOR_1, UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, &other_bricks, hangers).unwrap();
+        assert_eq!(&expected, base.warnings());
+
+        // Recoloring keeps the warnings of the base it started from
+        let base = base.recolor(|_, _, _| COLOR_2, UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE).unwrap();
+        assert_eq!(&expected, base.warnings());
+    }
+
+    #[test]
+    fn test_validate_generated_bases() {
+        let hangers = Hangers { brick: TWO_BY_ONE_BY_TWO_BRICK, from_top: 1, spacing: 8 };
+        let bases = [
+            Base::with_height(17, 19, 3, COLOR_1, UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, &[FOUR_BY_TWO_PLATE]).unwrap(),
+            Base::with_baseplates(20, 13, COLOR_1, &[EIGHT_BY_EIGHT_PLATE], BridgeLayer::Below, UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, &[]).unwrap(),
+            Base::with_hangers(16, 10, 2, COLOR_1, UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, &[FOUR_BY_TWO_PLATE], hangers).unwrap(),
+            Base::with_footprint(&Footprint::circle(9), COLOR_1, UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, &[]).unwrap(),
+            Base::new(6, 6, COLOR_1, UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, &[]).unwrap()
+                .recolor(|l, _, _| if l < 3 { COLOR_1 } else { COLOR_2 }, UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE)
+                .unwrap()
+        ];
+
+        for base in &bases {
+            assert!(base.validate().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_validate_finds_broken_bricks() {
+        let mut base = Base::new(4, 4, COLOR_1, UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, &[]).unwrap();
+        base.layers[1].push(FilledArea { brick: Brick::NonUnit(TWO_BY_ONE_PLATE), l: 3, w: 3, length: 2, width: 1 });
+        base.brick_colors.insert((1, 0, 1), COLOR_2);
+
+        assert_eq!(
+            vec![
+                ValidationIssue::OverlappingBricks(3, 3, 1),
+                ValidationIssue::OutOfBounds(3, 3, 1),
+                ValidationIssue::StrayColor(1, 0, 1)
+            ],
+            base.validate()
+        );
+    }
+
+    fn make_thick_mosaic(bricks: &[TestBrick<'static>]) -> Mosaic<u8, TestBrick<'static>, TestColor> {
+        let (img, palette) = make_test_img();
+        Mosaic::from_image(&img, &palette, |_, _, _| 2, |_, _, _, _| UNIT_BRICK).unwrap()
+            .reduce_bricks(bricks, &[])
+            .unwrap()
+    }
+
+    fn point_count(bricks: impl Iterator<Item=PlacedBrick<u8, TestBrick<'static>, TestColor>>) -> u32 {
+        bricks.map(|brick| brick.brick.length() as u32 * brick.brick.width() as u32 * brick.brick.height() as u32).sum()
+    }
+
+    #[test]
+    fn test_support_layer_joins_mosaic() {
+        let mosaic = make_thick_mosaic(&[TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE]);
+        let part_count = ConnectivityGraph::new(mosaic.iter()).components().len();
+
+        let supported = mosaic.clone().with_support_layer(COLOR_4, UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE).unwrap();
+
+        assert_eq!((mosaic.length(), mosaic.width()), (supported.length(), supported.width()));
+        assert_eq!(point_count(mosaic.iter()), point_count(supported.iter()));
+        assert!(supported.iter_layer(0).all(|brick| brick.color == COLOR_4));
+
+        let layer: Vec<_> = mosaic.iter_layer(1).collect();
+        assert_eq!(layer.len(), supported.iter_layer(1).count());
+        assert!(supported.iter_layer(1).all(|brick| layer.contains(&brick)));
+
+        // No single layer of plates can join every brick of this picture, which needs at least three parts
+        assert!(ConnectivityGraph::new(supported.iter()).components().len() < part_count);
+    }
+
+    #[test]
+    fn test_support_layer_joins_stacked_plates() {
+        let (_, palette) = make_test_img();
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image(&make_single_color_img(6, 4), &palette, |_, _, _| 2, |_, _, _, _| UNIT_BRICK)
+            .unwrap()
+            .reduce_bricks(&[TWO_BY_TWO_PLATE], &[])
+            .unwrap();
+        assert_eq!(6, ConnectivityGraph::new(mosaic.iter()).components().len());
+
+        let supported = mosaic.with_support_layer(COLOR_4, UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE).unwrap();
+
+        assert_eq!(48, point_count(supported.iter()));
+        assert_eq!(1, ConnectivityGraph::new(supported.iter()).components().len());
+    }
+
+    #[test]
+    fn test_support_layer_keeps_tall_bricks() {
+        let (_, palette) = make_test_img();
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image(&make_single_color_img(4, 4), &palette, |_, _, _| 2, |_, _, _, _| UNIT_BRICK)
+            .unwrap()
+            .reduce_bricks(&[TWO_BY_TWO_BY_TWO_BRICK], &[])
+            .unwrap();
+
+        let supported = mosaic.clone().with_support_layer(COLOR_4, UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE).unwrap();
+
+        let bricks: Vec<_> = mosaic.iter().collect();
+        assert_eq!(bricks.len(), supported.iter().count());
+        assert!(supported.iter().all(|brick| bricks.contains(&brick)));
+    }
+
+    #[test]
+    fn test_support_layer_keeps_uncovered_points() {
+        let (img, palette) = make_test_img();
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image(&img, &palette, |l, _, _| 1 + l % 2, |_, _, _, _| UNIT_BRICK)
+            .unwrap()
+            .reduce_bricks(&[TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE], &[])
+            .unwrap();
+
+        let supported = mosaic.clone().with_support_layer(COLOR_4, UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE).unwrap();
+
+        assert_eq!(point_count(mosaic.iter()), point_count(supported.iter()));
+        assert!(supported.validate().is_empty());
+        for l in 0..mosaic.length() {
+            for w in 0..mosaic.width() {
+                let expected = match l % 2 {
+                    0 => mosaic.brick_at(l, w, 0).unwrap().color,
+                    _ => COLOR_4
+                };
+                assert_eq!(expected, supported.brick_at(l, w, 0).unwrap().color);
+            }
+        }
+    }
+
+    #[test]
+    fn test_support_layer_leaves_flat_mosaic() {
+        let (img, palette) = make_test_img();
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image(&img, &palette, |_, _, _| 1, |_, _, _, _| UNIT_BRICK)
+            .unwrap()
+            .reduce_bricks(&[TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE], &[])
+            .unwrap();
+
+        let supported = mosaic.clone().with_support_layer(COLOR_4, UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE).unwrap();
+
+        let bricks: Vec<_> = mosaic.iter().collect();
+        assert_eq!(bricks.len(), supported.iter().count());
+        assert!(supported.iter().all(|brick| bricks.contains(&brick)));
+    }
+
+    #[test]
+    fn test_support_layer_with_bad_plate() {
+        let mosaic = make_thick_mosaic(&[]);
+
+        assert_eq!(
+            Err(BaseError::NotATwoByOnePlate(THREE_BY_ONE_PLATE)),
+            mosaic.with_support_layer(COLOR_4, UNIT_BRICK, THREE_BY_ONE_PLATE, TWO_BY_TWO_PLATE)
+        );
+    }
 }
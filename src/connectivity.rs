@@ -0,0 +1,341 @@
+use std::collections::{BTreeMap, VecDeque};
+use crate::{Base, Color, Mosaic, NonUnitBrick, PlacedBrick, UnitBrick};
+
+// ====================
+// PUBLIC STRUCTS
+// ====================
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum SeamDirection {
+    East,
+    South
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Seam {
+    pub l: u32,
+    pub w: u32,
+    pub h: u32,
+    pub direction: SeamDirection
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Connection {
+    pub brick: usize,
+    pub studs: u32
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct ConnectivityGraph<U, B, C> {
+    bricks: Vec<PlacedBrick<U, B, C>>,
+    above: Vec<Vec<Connection>>,
+    below: Vec<Vec<Connection>>,
+    cells: BTreeMap<(u32, u32, u32), usize>
+}
+
+impl<U: UnitBrick, B: NonUnitBrick<U>, C: Color> ConnectivityGraph<U, B, C> {
+    pub fn new(bricks: impl IntoIterator<Item=PlacedBrick<U, B, C>>) -> Self {
+        let bricks: Vec<PlacedBrick<U, B, C>> = bricks.into_iter().collect();
+        let mut cells = BTreeMap::new();
+
+        for (index, brick) in bricks.iter().enumerate() {
+            for_each_cell(brick, |l, w, h| {
+                cells.insert((h, l, w), index);
+            });
+        }
+
+        let mut above = vec![Vec::new(); bricks.len()];
+        let mut below = vec![Vec::new(); bricks.len()];
+
+        /* A brick clutches the bricks directly above it with every stud on its top face
+           that lies underneath one of those bricks */
+        for (index, brick) in bricks.iter().enumerate() {
+            let top_h = brick.h + brick.brick.height() as u32;
+            let mut studs_by_brick: BTreeMap<usize, u32> = BTreeMap::new();
+
+            for l in brick.l..(brick.l + brick.brick.length() as u32) {
+                for w in brick.w..(brick.w + brick.brick.width() as u32) {
+                    if let Some(&other) = cells.get(&(top_h, l, w)) {
+                        *studs_by_brick.entry(other).or_insert(0) += 1;
+                    }
+                }
+            }
+
+            for (other, studs) in studs_by_brick {
+                above[index].push(Connection { brick: other, studs });
+                below[other].push(Connection { brick: index, studs });
+            }
+        }
+
+        ConnectivityGraph { bricks, above, below, cells }
+    }
+
+    pub fn from_base_and_mosaic(base: &Base<U, B, C>, mosaic: &Mosaic<U, B, C>) -> Self {
+        let base_height = base.height();
+        ConnectivityGraph::new(base.iter().chain(mosaic.iter().map(|brick| PlacedBrick {
+            h: brick.h + base_height,
+            ..brick
+        })))
+    }
+
+    pub fn bricks(&self) -> &[PlacedBrick<U, B, C>] {
+        &self.bricks
+    }
+
+    pub fn above(&self, index: usize) -> &[Connection] {
+        &self.above[index]
+    }
+
+    pub fn below(&self, index: usize) -> &[Connection] {
+        &self.below[index]
+    }
+
+    pub fn brick_at(&self, l: u32, w: u32, h: u32) -> Option<usize> {
+        self.cells.get(&(h, l, w)).copied()
+    }
+
+    pub fn components(&self) -> Vec<Vec<usize>> {
+        let mut visited = vec![false; self.bricks.len()];
+        let mut components = Vec::new();
+        let mut to_visit = VecDeque::new();
+
+        for start in 0..self.bricks.len() {
+            if visited[start] {
+                continue;
+            }
+
+            let mut component = Vec::new();
+            visited[start] = true;
+            to_visit.push_back(start);
+
+            while let Some(index) = to_visit.pop_front() {
+                component.push(index);
+
+                for connection in self.above[index].iter().chain(self.below[index].iter()) {
+                    if !visited[connection.brick] {
+                        visited[connection.brick] = true;
+                        to_visit.push_back(connection.brick);
+                    }
+                }
+            }
+
+            component.sort();
+            components.push(component);
+        }
+
+        // List the largest components first so the main model is easy to find
+        components.sort_by_key(|component| std::cmp::Reverse(component.len()));
+        components
+    }
+
+    pub fn floating_bricks(&self) -> Vec<PlacedBrick<U, B, C>> {
+        let ground_h = match self.bricks.iter().map(|brick| brick.h).min() {
+            Some(h) => h,
+            None => return Vec::new()
+        };
+
+        // A component is floating when none of its bricks rest on the ground
+        self.components().into_iter()
+            .filter(|component| component.iter().all(|&index| self.bricks[index].h > ground_h))
+            .flat_map(|component| component.into_iter().map(|index| self.bricks[index]))
+            .collect()
+    }
+
+    pub fn single_stud_bricks(&self) -> Vec<PlacedBrick<U, B, C>> {
+        (0..self.bricks.len())
+            .filter(|&index| {
+                let studs: u32 = self.above[index].iter().chain(self.below[index].iter())
+                    .map(|connection| connection.studs)
+                    .sum();
+                studs == 1
+            })
+            .map(|index| self.bricks[index])
+            .collect()
+    }
+
+    pub fn unbridged_seams(&self, h: u32) -> Vec<Seam> {
+        let mut seams = Vec::new();
+
+        // Cells are ordered by height first, so the layer is one contiguous range
+        for (&(_, l, w), &index) in self.cells.range((h, 0, 0)..).take_while(|(&(cell_h, _, _), _)| cell_h == h) {
+            let east = (l + 1, w, SeamDirection::East);
+            let south = (l, w + 1, SeamDirection::South);
+
+            for (other_l, other_w, direction) in [east, south] {
+                let other = match self.brick_at(other_l, other_w, h) {
+                    Some(other) => other,
+                    None => continue
+                };
+
+                if other == index {
+                    continue;
+                }
+
+                // A seam is bridged when a single brick directly above or below covers both sides
+                let is_bridged = [h.checked_sub(1), h.checked_add(1)].into_iter()
+                    .flatten()
+                    .any(|bridge_h| {
+                        let bridge = self.brick_at(l, w, bridge_h);
+                        bridge.is_some() && bridge == self.brick_at(other_l, other_w, bridge_h)
+                    });
+
+                if !is_bridged {
+                    seams.push(Seam { l, w, h, direction });
+                }
+            }
+        }
+
+        seams.sort();
+        seams
+    }
+}
+
+impl<U: UnitBrick, B: NonUnitBrick<U>, C: Color> Base<U, B, C> {
+    pub fn weak_seams(&self) -> Vec<Seam> {
+        ConnectivityGraph::new(self.iter()).unbridged_seams(0)
+    }
+
+    // Seams that no layer of the base bridges are reported at the bottom layer
+    pub fn through_seams(&self) -> Vec<Seam> {
+        let graph = ConnectivityGraph::new(self.iter());
+        let mut seams = Vec::new();
+
+        for w in 0..self.width() {
+            for l in 0..self.length() {
+                let east = (l + 1, w, SeamDirection::East);
+                let south = (l, w + 1, SeamDirection::South);
+
+                for (other_l, other_w, direction) in [east, south] {
+                    if other_l >= self.length() || other_w >= self.width() {
+                        continue;
+                    }
+
+                    let is_bridged = (0..self.height()).any(|h| {
+                        let bridge = graph.brick_at(l, w, h);
+                        bridge.is_some() && bridge == graph.brick_at(other_l, other_w, h)
+                    });
+
+                    if !is_bridged {
+                        seams.push(Seam { l, w, h: 0, direction });
+                    }
+                }
+            }
+        }
+
+        seams.sort();
+        seams
+    }
+}
+
+// ====================
+// PRIVATE FUNCTIONS
+// ====================
+
+fn for_each_cell<U: UnitBrick, B: NonUnitBrick<U>, C>(brick: &PlacedBrick<U, B, C>, mut f: impl FnMut(u32, u32, u32)) {
+    for h in brick.h..(brick.h + brick.brick.height() as u32) {
+        for w in brick.w..(brick.w + brick.brick.width() as u32) {
+            for l in brick.l..(brick.l + brick.brick.length() as u32) {
+                f(l, w, h);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "default"))]
+mod tests {
+    use crate::{Base, Brick, ConnectivityGraph, Mosaic, PlacedBrick, Seam, SeamDirection};
+    use crate::palette::EuclideanDistancePalette;
+    use crate::tests::{COLOR_1, FOUR_BY_TWO_PLATE, TestBrick, TestColor, THREE_BY_ONE_PLATE, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, UNIT_BRICK};
+
+    fn place(l: u32, w: u32, h: u32, brick: TestBrick<'static>) -> PlacedBrick<u8, TestBrick<'static>, TestColor> {
+        PlacedBrick { l, w, h, brick: Brick::NonUnit(brick), color: COLOR_1 }
+    }
+
+    fn place_unit(l: u32, w: u32, h: u32) -> PlacedBrick<u8, TestBrick<'static>, TestColor> {
+        PlacedBrick { l, w, h, brick: Brick::Unit(UNIT_BRICK), color: COLOR_1 }
+    }
+
+    #[test]
+    fn test_empty_graph() {
+        let graph: ConnectivityGraph<u8, TestBrick, TestColor> = ConnectivityGraph::new(Vec::new());
+
+        assert!(graph.components().is_empty());
+        assert!(graph.floating_bricks().is_empty());
+        assert!(graph.single_stud_bricks().is_empty());
+        assert!(graph.unbridged_seams(0).is_empty());
+    }
+
+    #[test]
+    fn test_connections_count_overlapping_studs() {
+        let graph = ConnectivityGraph::new(vec![
+            place(0, 0, 0, FOUR_BY_TWO_PLATE),
+            place(2, 1, 1, TWO_BY_TWO_PLATE),
+            place_unit(0, 0, 1)
+        ]);
+
+        assert_eq!(2, graph.above(0).len());
+        assert_eq!(2, graph.above(0)[0].studs);
+        assert_eq!(1, graph.above(0)[1].studs);
+        assert_eq!(1, graph.below(1).len());
+        assert_eq!(0, graph.below(1)[0].brick);
+        assert_eq!(1, graph.components().len());
+        assert_eq!(vec![place_unit(0, 0, 1)], graph.single_stud_bricks());
+    }
+
+    #[test]
+    fn test_disconnected_components_and_floating_bricks() {
+        let graph = ConnectivityGraph::new(vec![
+            place(0, 0, 0, TWO_BY_TWO_PLATE),
+            place(0, 0, 1, TWO_BY_ONE_PLATE),
+            place(5, 5, 2, THREE_BY_ONE_PLATE),
+            place(4, 5, 3, TWO_BY_ONE_PLATE)
+        ]);
+
+        let components = graph.components();
+        assert_eq!(2, components.len());
+        assert_eq!(vec![0, 1], components[0]);
+        assert_eq!(vec![2, 3], components[1]);
+        assert_eq!(vec![place(5, 5, 2, THREE_BY_ONE_PLATE), place(4, 5, 3, TWO_BY_ONE_PLATE)], graph.floating_bricks());
+    }
+
+    #[test]
+    fn test_unbridged_seams() {
+        let graph = ConnectivityGraph::new(vec![
+            place(0, 0, 0, TWO_BY_ONE_PLATE),
+            place(2, 0, 0, TWO_BY_ONE_PLATE),
+            place(4, 0, 0, TWO_BY_ONE_PLATE),
+            place(1, 0, 1, TWO_BY_ONE_PLATE)
+        ]);
+
+        assert_eq!(vec![Seam { l: 3, w: 0, h: 0, direction: SeamDirection::East }], graph.unbridged_seams(0));
+        assert!(graph.unbridged_seams(1).is_empty());
+    }
+
+    #[test]
+    fn test_base_and_mosaic_are_connected() {
+        let (img, _) = crate::tests::make_test_img();
+        let palette = EuclideanDistancePalette::new(&[COLOR_1]);
+
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image(
+            &img,
+            &palette,
+            |_, _, _| 1,
+            |_, _, _, _| UNIT_BRICK
+        ).unwrap().reduce_bricks(&[TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE], &[]).unwrap();
+        let base = Base::new(
+            4,
+            5,
+            COLOR_1,
+            UNIT_BRICK,
+            TWO_BY_ONE_PLATE,
+            TWO_BY_TWO_PLATE,
+            &[]
+        ).unwrap();
+
+        let graph = ConnectivityGraph::from_base_and_mosaic(&base, &mosaic);
+
+        assert_eq!(1, graph.components().len());
+        assert!(graph.floating_bricks().is_empty());
+        assert!(graph.bricks().iter().any(|brick| brick.h == 2));
+    }
+}
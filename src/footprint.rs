@@ -0,0 +1,141 @@
+use crate::{Color, Image, Mosaic, NonUnitBrick, UnitBrick};
+
+// ====================
+// PUBLIC STRUCTS
+// ====================
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct Footprint {
+    cells: Vec<bool>,
+    length: u32,
+    width: u32
+}
+
+impl Footprint {
+    pub fn from_fn(length: u32, width: u32, contains_fn: impl Fn(u32, u32) -> bool) -> Self {
+        Footprint {
+            cells: (0..width).flat_map(|w| (0..length).map(move |l| (l, w)))
+                .map(|(l, w)| contains_fn(l, w))
+                .collect(),
+            length,
+            width
+        }
+    }
+
+    // Pixels at least as opaque as min_alpha are inside the footprint
+    pub fn from_alpha(image: &impl Image, min_alpha: u8) -> Self {
+        Footprint::from_fn(image.length(), image.width(), |l, w| image.pixel(l, w).alpha >= min_alpha)
+    }
+
+    // A stud is inside the circle when its center is
+    pub fn circle(diameter: u32) -> Self {
+        let radius = diameter as f64 / 2f64;
+
+        Footprint::from_fn(diameter, diameter, |l, w| {
+            let distance_l = l as f64 + 0.5 - radius;
+            let distance_w = w as f64 + 0.5 - radius;
+            distance_l * distance_l + distance_w * distance_w <= radius * radius
+        })
+    }
+
+    pub fn contains(&self, l: u32, w: u32) -> bool {
+        l < self.length && w < self.width && self.cells[w as usize * self.length as usize + l as usize]
+    }
+
+    pub fn length(&self) -> u32 {
+        self.length
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+}
+
+impl<U: UnitBrick, B: NonUnitBrick<U>, C: Color> Mosaic<U, B, C> {
+
+    /* Points outside the footprint are removed, and bricks that are only partly inside it are
+       replaced by unit bricks for their remaining points, so the mosaic can be reduced again */
+    pub fn mask(self, footprint: &Footprint) -> Self {
+        let length = self.length();
+        let width = self.width();
+
+        self.transform(
+            length,
+            width,
+            |l, w, h| match footprint.contains(l, w) {
+                true => Some((l, w, h)),
+                false => None
+            },
+            |brick| {
+                let is_inside = (brick.l..(brick.l + brick.brick.length() as u32))
+                    .all(|l| (brick.w..(brick.w + brick.brick.width() as u32)).all(|w| footprint.contains(l, w)));
+                match is_inside {
+                    true => Some(brick),
+                    false => None
+                }
+            }
+        )
+    }
+}
+
+#[cfg(all(test, feature = "default"))]
+mod tests {
+    use crate::{Footprint, Mosaic, NonUnitBrick};
+    use crate::tests::{make_single_color_img, make_test_img, TestBrick, TestColor, TestImage, TWO_BY_TWO_PLATE, UNIT_BRICK};
+    use crate::RawColor;
+
+    #[test]
+    fn test_circle() {
+        let circle = Footprint::circle(6);
+
+        assert_eq!((6, 6), (circle.length(), circle.width()));
+        assert!(circle.contains(2, 0));
+        assert!(!circle.contains(0, 0));
+        assert!(!circle.contains(5, 5));
+        assert!(circle.contains(0, 3));
+        assert!(!circle.contains(6, 3));
+    }
+
+    #[test]
+    fn test_footprint_from_alpha() {
+        let mut image = TestImage::new(3, 2);
+        image.put_pixel(1, 0, RawColor { red: 0, green: 0, blue: 0, alpha: 200 });
+        image.put_pixel(2, 1, RawColor { red: 0, green: 0, blue: 0, alpha: 100 });
+
+        let footprint = Footprint::from_alpha(&image, 128);
+
+        assert!(footprint.contains(1, 0));
+        assert!(!footprint.contains(2, 1));
+        assert!(!footprint.contains(0, 0));
+    }
+
+    #[test]
+    fn test_mask_removes_points_outside_footprint() {
+        let (_, palette) = make_test_img();
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image(
+            &make_single_color_img(6, 6),
+            &palette,
+            |_, _, _| 1,
+            |_, _, _, _| UNIT_BRICK
+        ).unwrap().reduce_bricks(&[TWO_BY_TWO_PLATE], &[]).unwrap();
+
+        let circle = Footprint::circle(6);
+        let masked = mosaic.mask(&circle);
+
+        assert_eq!((6, 6), (masked.length(), masked.width()));
+        let mut area = 0;
+        for brick in masked.iter() {
+            for l in brick.l..(brick.l + brick.brick.length() as u32) {
+                for w in brick.w..(brick.w + brick.brick.width() as u32) {
+                    assert!(circle.contains(l, w));
+                    area += 1;
+                }
+            }
+        }
+        assert_eq!((0..6).flat_map(|l| (0..6).map(move |w| (l, w))).filter(|&(l, w)| circle.contains(l, w)).count(), area);
+
+        // The middle of the circle keeps its large bricks
+        assert!(masked.iter().any(|brick| (brick.l, brick.w) == (2, 2) && brick.brick.length() == 2));
+        assert!(masked.iter().all(|brick| brick.brick.length() == 1 || brick.brick.length() == TWO_BY_TWO_PLATE.length()));
+    }
+}
@@ -0,0 +1,537 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::iter;
+use std::ops::Range;
+use crate::{Color, Mosaic, MosaicError, NonUnitBrick, PlacedBrick, UnitBrick, VoxelGrid};
+
+// ====================
+// PUBLIC STRUCTS
+// ====================
+
+#[non_exhaustive]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum FrameError {
+    EmptyProfile,
+    EmptyStep(usize),
+    TooLarge,
+    Mosaic(MosaicError)
+}
+
+// Mitred corners only meet along a staircase of single studs, so mitred sides rely on the base to hold them together
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum FrameCorner {
+    Butt,
+    Mitred
+}
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct Frame<U, B, C> {
+    bricks: Vec<PlacedBrick<U, B, C>>,
+    border: u32,
+    length: u32,
+    width: u32,
+    height: u32
+}
+
+impl<U: UnitBrick, B: NonUnitBrick<U>, C: Color> Frame<U, B, C> {
+
+    /* The profile lists the steps of the frame from its outer edge inward as (width, height)
+       pairs, so [(2, 6), (1, 3)] is two studs six plates tall with a one-stud step three plates
+       tall inside them. The frame surrounds the mosaic, so it is meant to sit on the outer ring
+       of a base as long and as wide as the frame, with the mosaic offset by the frame's border. */
+    pub fn new(mosaic_length: u32, mosaic_width: u32, profile: &[(u32, u32)], corner: FrameCorner, color: C,
+               one_by_one: U, bricks: &[B]) -> Result<Frame<U, B, C>, FrameError> {
+        if profile.is_empty() {
+            return Err(FrameError::EmptyProfile);
+        }
+
+        if let Some(index) = profile.iter().position(|&(width, height)| width == 0 || height == 0) {
+            return Err(FrameError::EmptyStep(index));
+        }
+
+        let border = profile.iter().try_fold(0u32, |border, &(width, _)| border.checked_add(width)).ok_or(FrameError::TooLarge)?;
+        let frame_size = |mosaic_size: u32| border.checked_mul(2).and_then(|borders| mosaic_size.checked_add(borders));
+        let length = frame_size(mosaic_length).ok_or(FrameError::TooLarge)?;
+        let width = frame_size(mosaic_width).ok_or(FrameError::TooLarge)?;
+        let height = profile.iter().map(|&(_, height)| height).max().unwrap();
+
+        /* Corners alternate from one course to the next, and a course is as tall as the tallest
+           brick allowed, so tall bricks can still be placed inside each course */
+        let course_height = bricks.iter()
+            .filter(|brick| brick.unit_brick() == one_by_one)
+            .map(|brick| brick.height() as u32)
+            .max()
+            .unwrap_or(1)
+            .clamp(1, height);
+
+        // Like reducing a mosaic, try bricks from the largest down with either side along the frame
+        let mut brick_sizes: Vec<(u32, u32, u32)> = bricks.iter()
+            .filter(|brick| brick.unit_brick() == one_by_one && brick.length() > 0 && brick.width() > 0)
+            .flat_map(|brick| {
+                let (length, width) = (brick.length() as u32, brick.width() as u32);
+                let volume = length * width * brick.height() as u32;
+                [(length, width, volume), (width, length, volume)]
+            })
+            .chain(iter::once((1, 1, 0)))
+            .collect();
+        brick_sizes.sort_by_key(|&(_, _, volume)| Reverse(volume));
+
+        let mut frame_bricks = Vec::new();
+        let mut cells_below = BTreeMap::new();
+
+        for start_h in (0..height).step_by(course_height as usize) {
+            let mut course_bricks = Vec::new();
+
+            // Each side is reduced on its own, so every joint between sides follows the corner style
+            for side in [Side::North, Side::South, Side::West, Side::East] {
+                let grid = SideGrid { side, profile, corner, color, one_by_one, border, course_height, start_h, length, width, height };
+                let mosaic: Mosaic<U, B, C> = Mosaic::from_voxels(&grid).map_err(FrameError::Mosaic)?;
+
+                let is_along_l = matches!(side, Side::North | Side::South);
+                let (along, across): (Vec<u32>, Vec<u32>) = mosaic.iter()
+                    .map(|brick| if is_along_l { (brick.l, brick.w) } else { (brick.w, brick.l) })
+                    .unzip();
+                let side_range = along.iter().min().map_or(0, |&start| start)..along.iter().max().map_or(0, |&end| end + 1);
+                let across_range = across.iter().min().map_or(0, |&start| start)..across.iter().max().map_or(0, |&end| end + 1);
+
+                /* Cutting the side in two before reducing it moves every seam after the cut. Bricks
+                   are laid from the start of each part, so where each cut would put them can be found
+                   without reducing the side. Keep the cut whose bricks line up least with the course
+                   below, then reduce the side once. */
+                let max_brick_length = brick_sizes.iter()
+                    .filter(|&&(_, brick_width, _)| brick_width <= across_range.len() as u32)
+                    .map(|&(brick_length, _, _)| brick_length)
+                    .max()
+                    .unwrap_or(1);
+                let cut = (side_range.start..(side_range.start + max_brick_length))
+                    .min_by_key(|&cut| {
+                        let areas = expected_areas(side_range.clone(), across_range.clone(), is_along_l, cut, &brick_sizes);
+                        let (stacked_bricks, seams) = aligned_seams(&areas, &cells_below);
+                        (stacked_bricks, seams, areas.len())
+                    })
+                    .unwrap();
+
+                let (before, after) = match is_along_l {
+                    true => (mosaic.clone().crop(0..cut, 0..width), mosaic.crop(cut..length, 0..width).translate(cut, 0)),
+                    false => (mosaic.clone().crop(0..length, 0..cut), mosaic.crop(0..length, cut..width).translate(0, cut))
+                };
+                let before = before.reduce_bricks(bricks, &[]).map_err(FrameError::Mosaic)?;
+                let after = after.reduce_bricks(bricks, &[]).map_err(FrameError::Mosaic)?;
+
+                course_bricks.extend(before.iter()
+                    .chain(after.iter())
+                    .map(|brick| PlacedBrick { h: brick.h + start_h, ..brick }));
+            }
+
+            let top_h = (start_h + course_height).min(height) - 1;
+            let top_areas: Vec<Area> = course_bricks.iter()
+                .filter(|brick| top_h >= brick.h && top_h < brick.h + brick.brick.height() as u32)
+                .map(|brick| (brick.l, brick.w, brick.brick.length() as u32, brick.brick.width() as u32))
+                .collect();
+            cells_below = cells_in_layer(&top_areas);
+            frame_bricks.append(&mut course_bricks);
+        }
+
+        Ok(Frame {
+            bricks: frame_bricks,
+            border,
+            length,
+            width,
+            height
+        })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item=PlacedBrick<U, B, C>> + '_ {
+        self.bricks.iter().copied()
+    }
+
+    pub fn border(&self) -> u32 {
+        self.border
+    }
+
+    pub fn length(&self) -> u32 {
+        self.length
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+}
+
+// ====================
+// PRIVATE TYPE ALIASES
+// ====================
+
+// An area covered by a brick as (l, w, length, width)
+type Area = (u32, u32, u32, u32);
+
+// ====================
+// PRIVATE FUNCTIONS
+// ====================
+
+/* Where bricks would be laid along the side from its start and from the cut. The reducer fills
+   the rows in bands as wide as the first brick that fits, and each band takes the largest brick
+   that fits the rest of its part. Sizes are given along and across the side. */
+fn expected_areas(side_range: Range<u32>, across_range: Range<u32>, is_along_l: bool, cut: u32,
+                  brick_sizes: &[(u32, u32, u32)]) -> Vec<Area> {
+    let fits = |max_length: u32, max_width: u32| brick_sizes.iter()
+        .find(|&&(brick_length, brick_width, _)| brick_length <= max_length && brick_width <= max_width)
+        .map_or((1, 1), |&(brick_length, brick_width, _)| (brick_length, brick_width));
+    let mut areas = Vec::new();
+    let mut band_start = across_range.start;
+
+    while band_start < across_range.end {
+        let (_, band_width) = fits(side_range.len() as u32, across_range.end - band_start);
+
+        for (start, end) in [(side_range.start, cut.min(side_range.end)), (cut, side_range.end)] {
+            let mut brick_start = start;
+
+            while brick_start < end {
+                let (brick_length, brick_width) = fits(end - brick_start, band_width);
+                for row in (band_start..(band_start + band_width)).step_by(brick_width as usize) {
+                    areas.push(match is_along_l {
+                        true => (brick_start, row, brick_length, brick_width),
+                        false => (row, brick_start, brick_width, brick_length)
+                    });
+                }
+                brick_start += brick_length;
+            }
+        }
+
+        band_start += band_width;
+    }
+
+    areas
+}
+
+// Which area covers each cell
+fn cells_in_layer(areas: &[Area]) -> BTreeMap<(u32, u32), usize> {
+    let mut cells = BTreeMap::new();
+
+    for (index, &(l, w, length, width)) in areas.iter().enumerate() {
+        for cell_l in l..(l + length) {
+            for cell_w in w..(w + width) {
+                cells.insert((cell_l, cell_w), index);
+            }
+        }
+    }
+
+    cells
+}
+
+// Bricks stacked on a brick with the same footprint are held only by each other, so they count first
+fn aligned_seams(areas: &[Area], cells_below: &BTreeMap<(u32, u32), usize>) -> (usize, usize) {
+    let cells = cells_in_layer(areas);
+    let is_seam = |cells: &BTreeMap<(u32, u32), usize>, point, other| match (cells.get(&point), cells.get(&other)) {
+        (Some(index), Some(other_index)) => index != other_index,
+        _ => false
+    };
+
+    let mut areas_below: BTreeMap<usize, usize> = BTreeMap::new();
+    for &index in cells_below.values() {
+        *areas_below.entry(index).or_insert(0) += 1;
+    }
+
+    let mut areas: BTreeMap<usize, (usize, Option<usize>, bool)> = BTreeMap::new();
+    for (point, &index) in &cells {
+        let below = cells_below.get(point).copied();
+        let (area, first_below, is_same) = areas.entry(index).or_insert((0, below, true));
+        *area += 1;
+        *is_same &= below.is_some() && below == *first_below;
+    }
+
+    let stacked_bricks = areas.values()
+        .filter(|&&(area, below, is_same)| is_same && below.map(|below| areas_below[&below]) == Some(area))
+        .count();
+
+    let seams = cells.keys()
+        .flat_map(|&(l, w)| [((l, w), (l + 1, w)), ((l, w), (l, w + 1))])
+        .filter(|&(point, other)| is_seam(&cells, point, other) && is_seam(cells_below, point, other))
+        .count();
+
+    (stacked_bricks, seams)
+}
+
+// ====================
+// PRIVATE STRUCTS
+// ====================
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+enum Side {
+    North,
+    South,
+    West,
+    East
+}
+
+struct SideGrid<'a, U, C> {
+    side: Side,
+    profile: &'a [(u32, u32)],
+    corner: FrameCorner,
+    color: C,
+    one_by_one: U,
+    border: u32,
+    course_height: u32,
+    start_h: u32,
+    length: u32,
+    width: u32,
+    height: u32
+}
+
+impl<U: UnitBrick, C: Color> SideGrid<'_, U, C> {
+    fn side_at(&self, l: u32, w: u32, h: u32) -> Option<Side> {
+        let distances = [
+            (w, Side::North),
+            (self.width - 1 - w, Side::South),
+            (l, Side::West),
+            (self.length - 1 - l, Side::East)
+        ];
+        let (distance, _) = *distances.iter().min().unwrap();
+
+        let step_height = self.profile.iter()
+            .scan(0, |step_end, &(width, height)| {
+                *step_end += width;
+                Some((*step_end, height))
+            })
+            .find(|&(step_end, _)| distance < step_end)
+            .map(|(_, height)| height)?;
+        if h >= step_height {
+            return None;
+        }
+
+        // Corners alternate between the north and south sides and the west and east sides
+        let north_south_first = (h / self.course_height).is_multiple_of(2);
+        let (first, second) = match north_south_first {
+            true => (&distances[0..2], &distances[2..4]),
+            false => (&distances[2..4], &distances[0..2])
+        };
+        let nearest = |distances: &[(u32, Side)]| *distances.iter().min().unwrap();
+        let (first_distance, first_side) = nearest(first);
+        let (second_distance, second_side) = nearest(second);
+
+        Some(match self.corner {
+            FrameCorner::Butt if first_distance < self.border => first_side,
+            FrameCorner::Butt => second_side,
+            FrameCorner::Mitred if first_distance <= second_distance => first_side,
+            FrameCorner::Mitred => second_side
+        })
+    }
+}
+
+impl<U: UnitBrick, C: Color> VoxelGrid<U, C> for SideGrid<'_, U, C> {
+    fn voxel(&self, l: u32, w: u32, h: u32) -> Option<(C, U)> {
+        match self.side_at(l, w, h + self.start_h) == Some(self.side) {
+            true => Some((self.color, self.one_by_one)),
+            false => None
+        }
+    }
+
+    fn length(&self) -> u32 {
+        self.length
+    }
+
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.course_height.min(self.height - self.start_h)
+    }
+}
+
+#[cfg(all(test, feature = "default"))]
+mod tests {
+    use std::collections::BTreeMap;
+    use super::{Side, SideGrid};
+    use crate::{Base, ConnectivityGraph, Frame, FrameCorner, FrameError, Mosaic, PlacedBrick};
+    use crate::tests::{COLOR_1, COLOR_2, FOUR_BY_TWO_PLATE, make_single_color_img, make_test_img, TestBrick, TestColor, TWO_BY_ONE_BY_TWO_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, UNIT_BRICK};
+
+    fn cell_counts(frame: &Frame<u8, TestBrick, TestColor>) -> BTreeMap<(u32, u32, u32), u32> {
+        let mut counts = BTreeMap::new();
+
+        for brick in frame.iter() {
+            for l in brick.l..(brick.l + brick.brick.length() as u32) {
+                for w in brick.w..(brick.w + brick.brick.width() as u32) {
+                    for h in brick.h..(brick.h + brick.brick.height() as u32) {
+                        *counts.entry((l, w, h)).or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+
+        counts
+    }
+
+    fn expected_height(frame: &Frame<u8, TestBrick, TestColor>, profile: &[(u32, u32)], l: u32, w: u32) -> u32 {
+        let distance = l.min(w).min(frame.length() - 1 - l).min(frame.width() - 1 - w);
+        let mut step_end = 0;
+
+        for &(width, height) in profile {
+            step_end += width;
+            if distance < step_end {
+                return height;
+            }
+        }
+
+        0
+    }
+
+    #[test]
+    fn test_frame_fills_profile() {
+        let profile = [(2, 3), (1, 1)];
+
+        for corner in [FrameCorner::Butt, FrameCorner::Mitred] {
+            let frame: Frame<u8, TestBrick, TestColor> = Frame::new(
+                6,
+                4,
+                &profile,
+                corner,
+                COLOR_2,
+                UNIT_BRICK,
+                &[TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, FOUR_BY_TWO_PLATE]
+            ).unwrap();
+
+            assert_eq!((3, 12, 10, 3), (frame.border(), frame.length(), frame.width(), frame.height()));
+            assert!(frame.iter().all(|brick| brick.color == COLOR_2));
+
+            let counts = cell_counts(&frame);
+            assert!(counts.values().all(|&count| count == 1));
+            for l in 0..frame.length() {
+                for w in 0..frame.width() {
+                    let height = (0..frame.height()).filter(|&h| counts.contains_key(&(l, w, h))).count() as u32;
+                    assert_eq!(expected_height(&frame, &profile, l, w), height);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_frame_corners_alternate() {
+        let grid = |side, corner| SideGrid {
+            side,
+            profile: &[(3, 2)],
+            corner,
+            color: COLOR_1,
+            one_by_one: UNIT_BRICK,
+            border: 3,
+            course_height: 1,
+            start_h: 0,
+            length: 8,
+            width: 8,
+            height: 2
+        };
+        let butt = grid(Side::North, FrameCorner::Butt);
+        let mitred = grid(Side::North, FrameCorner::Mitred);
+
+        assert_eq!(Some(Side::North), butt.side_at(0, 2, 0));
+        assert_eq!(Some(Side::West), butt.side_at(0, 3, 0));
+        assert_eq!(Some(Side::West), butt.side_at(2, 0, 1));
+        assert_eq!(Some(Side::North), butt.side_at(3, 0, 1));
+        assert_eq!(Some(Side::South), butt.side_at(7, 7, 0));
+        assert_eq!(None, butt.side_at(3, 3, 0));
+
+        assert_eq!(Some(Side::North), mitred.side_at(2, 1, 0));
+        assert_eq!(Some(Side::West), mitred.side_at(1, 2, 0));
+        assert_eq!(Some(Side::North), mitred.side_at(2, 2, 0));
+        assert_eq!(Some(Side::West), mitred.side_at(2, 2, 1));
+        assert_eq!(None, mitred.side_at(2, 2, 2));
+    }
+
+    #[test]
+    fn test_butt_frame_is_connected() {
+        for (length, width) in [(6, 6), (10, 3), (13, 9), (32, 32)] {
+            let frame: Frame<u8, TestBrick, TestColor> = Frame::new(
+                length,
+                width,
+                &[(2, 3)],
+                FrameCorner::Butt,
+                COLOR_1,
+                UNIT_BRICK,
+                &[TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, FOUR_BY_TWO_PLATE]
+            ).unwrap();
+
+            assert_eq!(1, ConnectivityGraph::new(frame.iter()).components().len());
+        }
+    }
+
+    #[test]
+    fn test_mitred_corner_follows_diagonal() {
+        let frame: Frame<u8, TestBrick, TestColor> = Frame::new(
+            2,
+            2,
+            &[(3, 1)],
+            FrameCorner::Mitred,
+            COLOR_1,
+            UNIT_BRICK,
+            &[]
+        ).unwrap();
+
+        // Only unit bricks are allowed, so the number of bricks in each side shows where the mitres are
+        let counts = frame.iter().fold(BTreeMap::new(), |mut counts, brick| {
+            *counts.entry(brick.w < 3 && brick.w <= brick.l.min(7 - brick.l)).or_insert(0) += 1;
+            counts
+        });
+        assert_eq!(Some(&(8 + 6 + 4)), counts.get(&true));
+        assert_eq!(64 - 4, frame.iter().count());
+    }
+
+    #[test]
+    fn test_frame_attaches_to_base() {
+        let (_, palette) = make_test_img();
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image(
+            &make_single_color_img(6, 6),
+            &palette,
+            |_, _, _| 1,
+            |_, _, _, _| UNIT_BRICK
+        ).unwrap().reduce_bricks(&[TWO_BY_TWO_PLATE], &[]).unwrap();
+        let frame: Frame<u8, TestBrick, TestColor> = Frame::new(
+            6,
+            6,
+            &[(2, 3)],
+            FrameCorner::Mitred,
+            COLOR_2,
+            UNIT_BRICK,
+            &[TWO_BY_ONE_PLATE, TWO_BY_ONE_BY_TWO_BRICK]
+        ).unwrap();
+        let base = Base::new(frame.length(), frame.width(), COLOR_1, UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, &[]).unwrap();
+
+        let offset = |brick: PlacedBrick<u8, TestBrick<'static>, TestColor>, border: u32| PlacedBrick {
+            l: brick.l + border,
+            w: brick.w + border,
+            h: brick.h + base.height(),
+            ..brick
+        };
+        let graph = ConnectivityGraph::new(base.iter()
+            .chain(frame.iter().map(|brick| offset(brick, 0)))
+            .chain(mosaic.iter().map(|brick| offset(brick, frame.border()))));
+
+        assert_eq!(1, graph.components().len());
+        assert!(frame.iter().any(|brick| brick.brick.height() == 2));
+    }
+
+    #[test]
+    fn test_invalid_profiles() {
+        let empty: Result<Frame<u8, TestBrick, TestColor>, _> = Frame::new(4, 4, &[], FrameCorner::Butt, COLOR_1, UNIT_BRICK, &[]);
+        let empty_step: Result<Frame<u8, TestBrick, TestColor>, _> = Frame::new(4, 4, &[(1, 2), (2, 0)], FrameCorner::Butt, COLOR_1, UNIT_BRICK, &[]);
+
+        assert_eq!(Err(FrameError::EmptyProfile), empty);
+        assert_eq!(Err(FrameError::EmptyStep(1)), empty_step);
+    }
+
+    #[test]
+    fn test_frame_too_large() {
+        let long: Result<Frame<u8, TestBrick, TestColor>, _> = Frame::new(u32::MAX - 3, 4, &[(2, 1)], FrameCorner::Butt, COLOR_1, UNIT_BRICK, &[]);
+        let wide: Result<Frame<u8, TestBrick, TestColor>, _> = Frame::new(4, u32::MAX, &[(1, 1)], FrameCorner::Butt, COLOR_1, UNIT_BRICK, &[]);
+        let thick: Result<Frame<u8, TestBrick, TestColor>, _> = Frame::new(4, 4, &[(u32::MAX, 1), (1, 1)], FrameCorner::Butt, COLOR_1, UNIT_BRICK, &[]);
+        let double: Result<Frame<u8, TestBrick, TestColor>, _> = Frame::new(0, 0, &[(u32::MAX / 2 + 1, 1)], FrameCorner::Butt, COLOR_1, UNIT_BRICK, &[]);
+
+        assert_eq!(Err(FrameError::TooLarge), long);
+        assert_eq!(Err(FrameError::TooLarge), wide);
+        assert_eq!(Err(FrameError::TooLarge), thick);
+        assert_eq!(Err(FrameError::TooLarge), double);
+    }
+}
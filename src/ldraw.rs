@@ -1,7 +1,7 @@
 use std::fmt::Display;
 use std::hash::{Hash, Hasher};
 use std::io::Write;
-use crate::{Brick, Mosaic, NonUnitBrick, PlacedBrick, RawColor, Srgba, UnitBrick};
+use crate::{Brick, Model, Mosaic, NonUnitBrick, PlacedBrick, RawColor, Srgba, ThreadSafe, UnitBrick};
 use crate::base::Base;
 
 // ====================
@@ -464,8 +464,8 @@ pub struct SubPartCommand<'a> {
 }
 
 impl SubPartCommand<'_> {
-    pub fn from_placement<'a, I: Copy + Eq, U: UnitBrick>(placement: &'a PlacedBrick<U, LdrawBrick<I, U>, LdrawColor>,
-                                                          file: &'a str, mosaic_width: u32) -> SubPartCommand<'a> {
+    pub fn from_placement<'a, I: Copy + Eq + ThreadSafe, U: UnitBrick>(placement: &'a PlacedBrick<U, LdrawBrick<I, U>, LdrawColor>,
+                                                                       file: &'a str, mosaic_width: u32) -> SubPartCommand<'a> {
         let l = placement.l;
         let w = placement.w;
         let h = placement.h;
@@ -598,7 +598,7 @@ impl<I: Copy + Eq + Hash, U: UnitBrick + Hash> Hash for LdrawBrick<I, U> {
     }
 }
 
-impl<I: Copy + Eq, U: UnitBrick> NonUnitBrick<U> for LdrawBrick<I, U> {
+impl<I: Copy + Eq + ThreadSafe, U: UnitBrick> NonUnitBrick<U> for LdrawBrick<I, U> {
     fn length(&self) -> u8 {
         self.length
     }
@@ -671,18 +671,24 @@ impl From<LdrawColor> for RawColor {
 // PUBLIC FUNCTIONS
 // ====================
 
-pub fn write_mosaic<'a, I: Copy + Eq, U: UnitBrick>(buffer: &mut impl Write, mosaic: &Mosaic<U, LdrawBrick<I, U>, LdrawColor>,
-                                                    id_fn: impl FnMut(Brick<U, LdrawBrick<I, U>>) -> &'a str,
-                                                    l: u32, w: u32, h: u32) -> std::io::Result<usize> {
+pub fn write_mosaic<'a, I: Copy + Eq + ThreadSafe, U: UnitBrick>(buffer: &mut impl Write, mosaic: &Mosaic<U, LdrawBrick<I, U>, LdrawColor>,
+                                                                 id_fn: impl FnMut(Brick<U, LdrawBrick<I, U>>) -> &'a str,
+                                                                 l: u32, w: u32, h: u32) -> std::io::Result<usize> {
     write(buffer, mosaic.iter(), id_fn, mosaic.width(), l, w, h)
 }
 
-pub fn write_base<'a, I: Copy + Eq, U: UnitBrick>(buffer: &mut impl Write, base: &Base<U, LdrawBrick<I, U>, LdrawColor>,
-                                                  id_fn: impl FnMut(Brick<U, LdrawBrick<I, U>>,) -> &'a str,
-                                                  l: u32, w: u32, h: u32) -> std::io::Result<usize> {
+pub fn write_base<'a, I: Copy + Eq + ThreadSafe, U: UnitBrick>(buffer: &mut impl Write, base: &Base<U, LdrawBrick<I, U>, LdrawColor>,
+                                                               id_fn: impl FnMut(Brick<U, LdrawBrick<I, U>>,) -> &'a str,
+                                                               l: u32, w: u32, h: u32) -> std::io::Result<usize> {
     write(buffer, base.iter(), id_fn, base.width(), l, w, h)
 }
 
+pub fn write_model<'a, I: Copy + Eq + ThreadSafe, U: UnitBrick>(buffer: &mut impl Write, model: &Model<U, LdrawBrick<I, U>, LdrawColor>,
+                                                                id_fn: impl FnMut(Brick<U, LdrawBrick<I, U>>) -> &'a str,
+                                                                l: u32, w: u32, h: u32) -> std::io::Result<usize> {
+    write(buffer, model.iter(), id_fn, model.width(), l, w, h)
+}
+
 // ====================
 // PRIVATE CONSTANTS
 // ====================
@@ -705,9 +711,9 @@ const ROTATED_TRANSFORM: [[f64; 4]; 4] = [
 // PRIVATE FUNCTIONS
 // ====================
 
-fn write<'a, I: Copy + Eq, U: UnitBrick>(buffer: &mut impl Write, bricks: impl Iterator<Item=PlacedBrick<U, LdrawBrick<I, U>, LdrawColor>>,
-                                         mut id_fn: impl FnMut(Brick<U, LdrawBrick<I, U>>) -> &'a str, mosaic_width: u32,
-                                         l: u32, w: u32, h: u32) -> std::io::Result<usize> {
+fn write<'a, I: Copy + Eq + ThreadSafe, U: UnitBrick>(buffer: &mut impl Write, bricks: impl Iterator<Item=PlacedBrick<U, LdrawBrick<I, U>, LdrawColor>>,
+                                                      mut id_fn: impl FnMut(Brick<U, LdrawBrick<I, U>>) -> &'a str, mosaic_width: u32,
+                                                      l: u32, w: u32, h: u32) -> std::io::Result<usize> {
     let mut bytes = 0;
 
     for placement in bricks {
@@ -7,12 +7,31 @@ pub mod image;
 #[cfg(feature = "ldraw")]
 pub mod ldraw;
 
+#[cfg(feature = "vox")]
+pub mod vox;
+
+#[cfg(feature = "mesh")]
+pub mod mesh;
+
 mod base;
+mod connectivity;
+mod footprint;
+mod frame;
+mod model;
+mod panel;
 
 pub use base::*;
+pub use connectivity::*;
+pub use footprint::*;
+pub use frame::*;
+pub use model::*;
+pub use panel::*;
 use std::cmp::Ordering;
 use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::ops::Range;
 use boolvec::BoolVec;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 // This API uses l, w, and h coordinate axes, which refer to length, width, and height,
 // respectively. A brick's length refers to its size along the l axis, a brick's
@@ -43,13 +62,24 @@ pub type RawColor = Srgba<u8>;
 // PUBLIC TRAITS
 // ====================
 
-pub trait Color: Copy + Default + Eq + Into<RawColor> {}
-impl<T: Copy + Default + Eq + Into<RawColor>> Color for T {}
+// Types only need to be shared between threads when sections and chunks are built in parallel
+#[cfg(feature = "parallel")]
+pub trait ThreadSafe: Send + Sync {}
+#[cfg(feature = "parallel")]
+impl<T: Send + Sync> ThreadSafe for T {}
+
+#[cfg(not(feature = "parallel"))]
+pub trait ThreadSafe {}
+#[cfg(not(feature = "parallel"))]
+impl<T> ThreadSafe for T {}
+
+pub trait Color: Copy + Default + Eq + Into<RawColor> + ThreadSafe {}
+impl<T: Copy + Default + Eq + Into<RawColor> + ThreadSafe> Color for T {}
 
-pub trait UnitBrick: Copy + Eq + Ord {}
-impl<T: Copy + Eq + Ord> UnitBrick for T {}
+pub trait UnitBrick: Copy + Eq + Ord + ThreadSafe {}
+impl<T: Copy + Eq + Ord + ThreadSafe> UnitBrick for T {}
 
-pub trait NonUnitBrick<U>: Copy + Eq {
+pub trait NonUnitBrick<U>: Copy + Eq + ThreadSafe {
     fn length(&self) -> u8;
 
     fn width(&self) -> u8;
@@ -75,6 +105,26 @@ pub trait Palette<C> {
     fn nearest(&self, color: RawColor) -> Option<C>;
 }
 
+pub trait VoxelGrid<U, C> {
+    fn voxel(&self, l: u32, w: u32, h: u32) -> Option<(C, U)>;
+
+    fn length(&self) -> u32;
+
+    fn width(&self) -> u32;
+
+    fn height(&self) -> u32;
+}
+
+pub trait TileSource {
+
+    // Pixels are returned row by row, so the pixel at (l, w) in the tile is at w * length + l
+    fn tile(&mut self, l: u32, w: u32, length: u32, width: u32) -> Vec<RawColor>;
+
+    fn length(&self) -> u32;
+
+    fn width(&self) -> u32;
+}
+
 // ====================
 // PUBLIC STRUCTS
 // ====================
@@ -90,7 +140,34 @@ pub struct Srgba<T> {
 #[non_exhaustive]
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub enum MosaicError {
-    PointerTooSmall
+    PointerTooSmall,
+    TileTooSmall
+}
+
+/* Inputs that are allowed but probably not what the caller meant. DefaultColor counts the pixels
+   the palette had no color for, which took the default color instead, and gives the l and w of
+   the first of them by row. Bricks in ZeroSizedBrick and BrickTooTall are never placed, either
+   because they have no volume or because they are taller than every chunk of their unit brick. */
+#[non_exhaustive]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum MosaicWarning<B> {
+    EmptyImage,
+    DefaultColor(u64, u32, u32),
+    ZeroSizedBrick(B),
+    BrickTooTall(B)
+}
+
+/* Points that are covered by no brick or by several bricks are given by their own coordinates,
+   and every other issue by the l, w and h of the brick that causes it */
+#[non_exhaustive]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum ValidationIssue {
+    UncoveredPoint(u32, u32, u32),
+    OverlappingBricks(u32, u32, u32),
+    BrickOutsideChunk(u32, u32, u32),
+    WrongUnitBrick(u32, u32, u32),
+    OutOfBounds(u32, u32, u32),
+    StrayColor(u32, u32, u32)
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
@@ -145,78 +222,266 @@ pub struct PlacedBrick<U, B, C> {
     pub color: C
 }
 
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Edit<U, C> {
+    Recolor { l: Range<u32>, w: Range<u32>, color: C },
+    SetColor { l: u32, w: u32, color: C },
+    SetHeight { l: u32, w: u32, height: u32, brick: U, color: C },
+    ReplaceColor { from: C, to: C }
+}
+
+impl<U, C: Eq> Edit<U, C> {
+    fn touches_column(&self, l: u32, w: u32) -> bool {
+        match self {
+            Edit::Recolor { l: edit_l, w: edit_w, .. } => edit_l.contains(&l) && edit_w.contains(&w),
+            Edit::SetColor { l: edit_l, w: edit_w, .. } => (*edit_l, *edit_w) == (l, w),
+            Edit::SetHeight { l: edit_l, w: edit_w, .. } => (*edit_l, *edit_w) == (l, w),
+            Edit::ReplaceColor { .. } => false
+        }
+    }
+
+    fn touches_color(&self, color: C) -> bool {
+        match self {
+            Edit::ReplaceColor { from, .. } => *from == color,
+            _ => false
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct ChunkStrength {
+    pub l: u32,
+    pub w: u32,
+    pub h: u32,
+    pub length: u8,
+    pub width: u8,
+    pub height: u8,
+    pub seams: u32,
+    pub aligned_seams: u32
+}
+
+impl ChunkStrength {
+    pub fn score(&self) -> f64 {
+
+        /* The score is the fraction of seams above the chunk's bottom layer that are bridged
+           by the layer below them, from 0 (every seam runs straight down) to 1 (none do). */
+        match self.seams {
+            0 => 1f64,
+            seams => 1f64 - self.aligned_seams as f64 / seams as f64
+        }
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
 pub struct Mosaic<U, B, C> {
     sections: Vec<Section<U, B, C>>,
+    index: ChunkIndex,
     length: u32,
-    width: u32
+    width: u32,
+    warnings: Vec<MosaicWarning<B>>
 }
 
 impl<U: UnitBrick, B: NonUnitBrick<U>, C: Color> Mosaic<U, B, C> {
-    pub fn from_image<I: Image>(image: &I,
-                                palette: &impl Palette<C>,
-                                height_fn: impl Fn(u32, u32, C) -> u32,
-                                brick_fn: impl Fn(u32, u32, u32, C) -> U) -> Result<Self, MosaicError> {
+    pub fn from_image<I: Image + ThreadSafe>(image: &I,
+                                             palette: &(impl Palette<C> + ThreadSafe),
+                                             height_fn: impl Fn(u32, u32, C) -> u32 + ThreadSafe,
+                                             brick_fn: impl Fn(u32, u32, u32, C) -> U + ThreadSafe) -> Result<Self, MosaicError> {
+        Mosaic::from_relief(image, palette, height_fn, brick_fn, 1, |_, _, _, _, color| Some(color))
+    }
+
+    pub fn from_image_with_filler<I: Image + ThreadSafe>(image: &I,
+                                                         palette: &(impl Palette<C> + ThreadSafe),
+                                                         height_fn: impl Fn(u32, u32, C) -> u32 + ThreadSafe,
+                                                         brick_fn: impl Fn(u32, u32, u32, C) -> U + ThreadSafe,
+                                                         filler: C) -> Result<Self, MosaicError> {
+
+        /* Only the top of each column and the sides that are not covered by a neighboring column
+           can be seen, so every other point may use the filler color. Hidden points then join
+           into large chunks that can be reduced to far fewer bricks. */
+        Mosaic::from_relief(image, palette, height_fn, brick_fn, 1, |relief, l, w, h, color| {
+            match relief.is_exposed(l, w, h) {
+                true => Some(color),
+                false => Some(filler)
+            }
+        })
+    }
+
+    pub fn from_image_hollow<I: Image + ThreadSafe>(image: &I,
+                                                    palette: &(impl Palette<C> + ThreadSafe),
+                                                    height_fn: impl Fn(u32, u32, C) -> u32 + ThreadSafe,
+                                                    brick_fn: impl Fn(u32, u32, u32, C) -> U + ThreadSafe,
+                                                    wall_thickness: u32,
+                                                    support_spacing: u32) -> Result<Self, MosaicError> {
+        /* Every point is within the image's longest side of a point outside it, so any thicker
+           wall already fills the whole mosaic. Each section is also padded by the wall thickness,
+           so walls are capped at a section's size to keep the padding no larger than the section. */
+        let wall_thickness = wall_thickness.clamp(1, image.length().max(image.width()).clamp(1, u8::MAX as u32));
+
+        /* A lid made from a single layer would fall apart because bricks in the same layer only
+           connect through the layers above and below them. Keep at least two layers on top so
+           the lid's bricks clutch each other. */
+        let lid_thickness = wall_thickness.max(2);
+
+        Mosaic::from_relief(image, palette, height_fn, brick_fn, wall_thickness, |relief, l, w, h, color| {
+            let (image_l, image_w) = relief.image_pos(l, w);
+
+            // Columns on a grid of pillars stay solid to hold the lid up from the base
+            let is_support = support_spacing > 0
+                && image_l.is_multiple_of(support_spacing)
+                && image_w.is_multiple_of(support_spacing);
+
+            let is_shell = h + lid_thickness >= relief.height(l, w) || relief.is_near_side(l, w, h);
+
+            match is_shell || is_support {
+                true => Some(color),
+                false => None
+            }
+        })
+    }
+
+    fn from_relief<I: Image + ThreadSafe>(image: &I,
+                                          palette: &(impl Palette<C> + ThreadSafe),
+                                          height_fn: impl Fn(u32, u32, C) -> u32 + ThreadSafe,
+                                          brick_fn: impl Fn(u32, u32, u32, C) -> U + ThreadSafe,
+                                          padding: u32,
+                                          color_fn: impl Fn(&Relief, usize, usize, u32, C) -> Option<C> + ThreadSafe) -> Result<Self, MosaicError> {
         let section_size = u8::MAX as u32;
-        let section_images = Mosaic::<U, B, C>::make_sections::<I>(image, section_size);
-        let mut sections = Vec::with_capacity(section_images.len());
+        let section_images = Mosaic::<U, B, C>::make_sections(image.length(), image.width(), section_size);
 
         /* Dividing the mosaic into sections allows u8s to be used for brick coordinates,
            significantly reducing memory required. It also limits memory to the amount required
-           for the section while the mosaic is being generated and improves spatial locality. */
-        for (section_l, section_w, section_length, section_width) in section_images {
-
-            // Cache colors and heights so they do not need to be recomputed for each (l, w)
-            let raw_colors: Pixels<RawColor> = Pixels::<RawColor>::from_fn(
-                |l, w| image.pixel(l as u32 + section_l, w as u32 + section_w),
-                section_length as usize,
-                section_width as usize
-            );
-            let colors = raw_colors.with_palette(palette);
-
-            let height_map = HeightMap::from_fn(
-                |l, w| height_fn(l as u32 + section_l, w as u32 + section_w, colors.value(l, w)),
-                section_length as usize,
-                section_width as usize
-            );
-            let max_height = height_map.max().map_or(0, |max| *max);
+           for the section while the mosaic is being generated and improves spatial locality.
+           Sections are independent, so they may also be built at the same time. */
+        let sections_by_image = map_in_order(section_images, |section| Mosaic::build_section(
+            image,
+            palette,
+            &height_fn,
+            &brick_fn,
+            padding,
+            &color_fn,
+            section,
+            section_size
+        ));
+
+        let mut sections = Vec::new();
+        let mut warnings = Vec::new();
+        if image.length() == 0 || image.width() == 0 {
+            warnings.push(MosaicWarning::EmptyImage);
+        }
+
+        for section in sections_by_image {
+            let (mut section, section_warnings) = section?;
+            sections.append(&mut section);
+            add_warnings(&mut warnings, section_warnings);
+        }
+
+        Ok(Mosaic::new(Mosaic::merge_across_sections(sections, section_size), image.length(), image.width(), warnings))
+    }
+
+    pub fn from_voxels(grid: &(impl VoxelGrid<U, C> + ThreadSafe)) -> Result<Self, MosaicError> {
+        let section_size = u8::MAX as u32;
+        let section_images = Mosaic::<U, B, C>::make_sections(grid.length(), grid.width(), section_size);
 
+        /* Points in the grid are not limited to a height field, so every layer of a section may
+           have holes and overhangs. The search that builds chunks already skips empty points, so
+           each layer only needs to be bounded by the height of the grid. */
+        let sections_by_grid = map_in_order(section_images, |(section_l, section_w, section_length, section_width)| {
+            let mut sections = Vec::new();
             let mut section_h = 0;
 
-            while section_h < max_height {
-                let section_height = section_size.min(max_height - section_h);
+            while section_h < grid.height() {
+                let section_height = section_size.min(grid.height() - section_h);
 
-                // Build contiguous 3D chunks (with same color and brick) of the mosaic
                 let chunks = Mosaic::<U, B, C>::build_chunks(
                     section_length,
                     section_width,
                     section_height as u8,
-                    |l, w| {
-                        let height = height_map.value(l as usize, w as usize);
-                        match height > section_h {
-                            true => section_size.min(height - section_h) as u8,
-                            false => 0
-                        }
-                    },
-                    |l, w, h, color| brick_fn(
-                        l as u32 + section_l,
-                        w as u32 + section_w,
-                        h as u32 + section_h,
-                        color
-                    ),
-                    |l, w| colors.value(l as usize, w as usize)
+                    |_, _| section_height as u8,
+                    |l, w, h| grid.voxel(l as u32 + section_l, w as u32 + section_w, h as u32 + section_h)
+                        .map(|(color, brick)| (brick, color))
                 )?;
 
                 sections.push((section_l, section_w, section_h, chunks));
 
                 section_h += section_height;
             }
+
+            Ok(sections)
+        });
+
+        let mut sections = Vec::new();
+        for section in sections_by_grid {
+            sections.append(&mut section?);
         }
 
-        Ok(Mosaic::new(sections, image.length(), image.width()))
+        let warnings = match grid.length() == 0 || grid.width() == 0 || grid.height() == 0 {
+            true => vec![MosaicWarning::EmptyImage],
+            false => Vec::new()
+        };
+
+        Ok(Mosaic::new(Mosaic::merge_across_sections(sections, section_size), grid.length(), grid.width(), warnings))
+    }
+
+    /* Unlike from_image, chunks are never merged across the edges between tiles, so bricks in the
+       reduced mosaics stop at every tile edge. Those seams go through every layer, which makes a
+       tall mosaic weaker along them than the same mosaic built from the whole image. */
+    pub fn from_tiles<'a, T: TileSource>(source: &'a mut T,
+                                         palette: &'a impl Palette<C>,
+                                         height_fn: impl Fn(u32, u32, C) -> u32 + 'a,
+                                         brick_fn: impl Fn(u32, u32, u32, C) -> U + 'a) -> impl Iterator<Item=Result<Self, MosaicError>> + 'a {
+        let section_size = u8::MAX as u32;
+        let length = source.length();
+        let width = source.width();
+
+        /* Visit sections row by row, so a source that reads the image from top to bottom only
+           needs to keep one band of rows in memory at a time */
+        let mut section_images = Mosaic::<U, B, C>::make_sections(length, width, section_size);
+        section_images.sort_by_key(|&(section_l, section_w, _, _)| (section_w, section_l));
+
+        /* Each section is built from its own tile and emitted as a mosaic as soon as it is done,
+           so memory only depends on the size of a section. Chunks are not merged across section
+           edges because the neighboring sections may not have been read yet. */
+        section_images.into_iter().map(move |section| {
+            let (section_l, section_w, section_length, section_width) = section;
+            let pixels = source.tile(section_l, section_w, section_length as u32, section_width as u32);
+
+            // A short tile would leave some points without a pixel
+            if pixels.len() < section_length as usize * section_width as usize {
+                return Err(MosaicError::TileTooSmall);
+            }
+
+            let tile = Tile {
+                l: section_l,
+                w: section_w,
+                tile_length: section_length as u32,
+                image_length: length,
+                image_width: width,
+                pixels
+            };
+
+            let (sections, warnings) = Mosaic::build_section(
+                &tile,
+                palette,
+                &height_fn,
+                &brick_fn,
+                0,
+                &|_, _, _, _, color| Some(color),
+                section,
+                section_size
+            )?;
+
+            Ok(Mosaic::new(sections, length, width, warnings))
+        })
     }
 
     pub fn reduce_bricks(self, bricks: &[B], exclusions: &[(B, C)]) -> Result<Self, MosaicError> {
+        self.reduce_bricks_staggered(bricks, exclusions, 0)
+    }
+
+    pub fn reduce_bricks_staggered(self, bricks: &[B], exclusions: &[(B, C)], seam_penalty: u32) -> Result<Self, MosaicError> {
+        let mut warnings = self.warnings.clone();
+        add_warnings(&mut warnings, self.brick_warnings(bricks));
+
         let bricks_by_type: BTreeMap<U, Vec<VolumeSortedBrick<U, B>>> = bricks.iter()
             .fold(BTreeMap::new(), |mut partitions, &brick| {
 
@@ -249,12 +514,11 @@ impl<U: UnitBrick, B: NonUnitBrick<U>, C: Color> Mosaic<U, B, C> {
             })
             .collect();
 
-        let chunks = self.sections.into_iter()
-            .map(|(l, w, h, chunks)| (
+        let chunks = map_in_order(self.sections, |(l, w, h, chunks)| (
                 l,
                 w,
                 h,
-                chunks.into_iter().map(|chunk| {
+                map_in_order(chunks, |chunk| {
                     if bricks_by_type.contains_key(&chunk.unit_brick) {
                         let bricks_by_height: Vec<VolumeSortedBrick<U, B>> = bricks_by_type[&chunk.unit_brick].iter()
                             .filter(|brick| {
@@ -266,15 +530,237 @@ impl<U: UnitBrick, B: NonUnitBrick<U>, C: Color> Mosaic<U, B, C> {
                             })
                             .copied()
                             .collect();
-                        chunk.reduce_bricks(&bricks_by_height)
+                        chunk.reduce_bricks(&bricks_by_height, seam_penalty)
                     } else {
                         chunk
                     }
-                }).collect()
+                })
+            ));
+
+        Ok(Mosaic::new(chunks, self.length, self.width, warnings))
+    }
+
+    pub fn edit(self, edits: &[Edit<U, C>], bricks: &[B], exclusions: &[(B, C)]) -> Result<Self, MosaicError> {
+        let section_size = u8::MAX as u32;
+        let mut points = BTreeMap::new();
+        let mut sections = Vec::with_capacity(self.sections.len());
+
+        /* Only chunks with a point that an edit could change are taken apart. Every other chunk
+           keeps its bricks exactly as they are, so steps that were already built stay valid. */
+        for (section_l, section_w, section_h, chunks) in self.sections {
+            let (affected, kept): (Vec<_>, Vec<_>) = chunks.into_iter().partition(|chunk| {
+                edits.iter().any(|edit| edit.touches_color(chunk.color))
+                    || chunk.points().any(|(l, w, _)| edits.iter().any(|edit| edit.touches_column(
+                        section_l + chunk.l as u32 + l as u32,
+                        section_w + chunk.w as u32 + w as u32
+                    )))
+            });
+
+            for chunk in affected {
+                for (l, w, h) in chunk.points() {
+                    points.insert(
+                        (section_l + chunk.l as u32 + l as u32, section_w + chunk.w as u32 + w as u32, section_h + chunk.h as u32 + h as u32),
+                        (chunk.unit_brick, chunk.color)
+                    );
+                }
+            }
+
+            sections.push((section_l, section_w, section_h, kept));
+        }
+
+        for edit in edits {
+            match edit {
+                Edit::Recolor { l, w, color } => points.iter_mut()
+                    .filter(|((point_l, point_w, _), _)| l.contains(point_l) && w.contains(point_w))
+                    .for_each(|(_, (_, point_color))| *point_color = *color),
+                Edit::SetColor { l, w, color } => points.iter_mut()
+                    .filter(|((point_l, point_w, _), _)| (point_l, point_w) == (l, w))
+                    .for_each(|(_, (_, point_color))| *point_color = *color),
+                Edit::SetHeight { l, w, height, brick, color } => {
+                    if *l >= self.length || *w >= self.width {
+                        continue;
+                    }
+
+                    points.retain(|&(point_l, point_w, point_h), _| (point_l, point_w) != (*l, *w) || point_h < *height);
+                    for h in 0..*height {
+                        points.entry((*l, *w, h)).or_insert((*brick, *color));
+                    }
+                },
+                Edit::ReplaceColor { from, to } => points.values_mut()
+                    .filter(|(_, point_color)| point_color == from)
+                    .for_each(|(_, point_color)| *point_color = *to)
+            }
+        }
+
+        // Rebuild chunks from the edited points only, one section at a time
+        let mut points_by_section: BTreeMap<_, BTreeMap<_, _>> = BTreeMap::new();
+        for ((l, w, h), voxel) in points {
+            let origin = (l - l % section_size, w - w % section_size, h - h % section_size);
+            points_by_section.entry(origin).or_default()
+                .insert(((l - origin.0) as u8, (w - origin.1) as u8, (h - origin.2) as u8), voxel);
+        }
+
+        let mut new_sections = Vec::new();
+        for ((section_l, section_w, section_h), section_points) in points_by_section {
+            let length = section_points.keys().map(|&(l, _, _)| l).max().unwrap() + 1;
+            let width = section_points.keys().map(|&(_, w, _)| w).max().unwrap() + 1;
+            let height = section_points.keys().map(|&(_, _, h)| h).max().unwrap() + 1;
+
+            let chunks = Mosaic::<U, B, C>::build_chunks(
+                length,
+                width,
+                height,
+                |_, _| height,
+                |l, w, h| section_points.get(&(l, w, h)).copied()
+            )?;
+            new_sections.push((section_l, section_w, section_h, chunks));
+        }
+
+        let new_mosaic = Mosaic::new(Mosaic::merge_across_sections(new_sections, section_size), self.length, self.width, Vec::new())
+            .reduce_bricks(bricks, exclusions)?;
+
+        for (section_l, section_w, section_h, mut chunks) in new_mosaic.sections {
+            match sections.iter().position(|&(l, w, h, _)| (l, w, h) == (section_l, section_w, section_h)) {
+                Some(index) => sections[index].3.append(&mut chunks),
+                None => sections.push((section_l, section_w, section_h, chunks))
+            }
+        }
+
+        sections.retain(|(_, _, _, chunks)| !chunks.is_empty());
+
+        // Edited points may use a unit brick the mosaic did not have, so the bricks are checked against the edited chunks
+        let mut warnings = self.warnings;
+        add_warnings(&mut warnings, new_mosaic.warnings);
+        Ok(Mosaic::new(sections, self.length, self.width, warnings))
+    }
+
+    pub fn rotate_90(self) -> Self {
+        let (length, width) = (self.length, self.width);
+
+        // Rotate clockwise when seen from above, so east becomes south and south becomes west
+        self.transform(
+            width,
+            length,
+            |l, w, h| Some((width - 1 - w, l, h)),
+            |brick| Some(PlacedBrick {
+                l: width - brick.w - brick.brick.width() as u32,
+                w: brick.l,
+                brick: brick.brick.rotate_90(),
+                ..brick
+            })
+        )
+    }
+
+    pub fn rotate_180(self) -> Self {
+        self.rotate_90().rotate_90()
+    }
+
+    pub fn rotate_270(self) -> Self {
+        self.rotate_90().rotate_90().rotate_90()
+    }
+
+    pub fn mirror_l(self) -> Self {
+        let (length, width) = (self.length, self.width);
+        self.transform(
+            length,
+            width,
+            |l, w, h| Some((length - 1 - l, w, h)),
+            |brick| Some(PlacedBrick { l: length - brick.l - brick.brick.length() as u32, ..brick })
+        )
+    }
+
+    pub fn mirror_w(self) -> Self {
+        let (length, width) = (self.length, self.width);
+        self.transform(
+            length,
+            width,
+            |l, w, h| Some((l, width - 1 - w, h)),
+            |brick| Some(PlacedBrick { w: width - brick.w - brick.brick.width() as u32, ..brick })
+        )
+    }
+
+    pub fn translate(self, l: u32, w: u32) -> Self {
+        let (length, width) = (self.length + l, self.width + w);
+        self.transform(
+            length,
+            width,
+            |point_l, point_w, h| Some((point_l + l, point_w + w, h)),
+            |brick| Some(PlacedBrick { l: brick.l + l, w: brick.w + w, ..brick })
+        )
+    }
+
+    pub fn crop(self, l: Range<u32>, w: Range<u32>) -> Self {
+        let l = l.start.min(self.length)..l.end.min(self.length);
+        let w = w.start.min(self.width)..w.end.min(self.width);
+        let length = l.end.saturating_sub(l.start);
+        let width = w.end.saturating_sub(w.start);
+
+        // Bricks that cross the edge of the crop are replaced by unit bricks for the points inside
+        self.transform(
+            length,
+            width,
+            |point_l, point_w, h| match l.contains(&point_l) && w.contains(&point_w) {
+                true => Some((point_l - l.start, point_w - w.start, h)),
+                false => None
+            },
+            |brick| {
+                let is_inside = brick.l >= l.start && brick.l + brick.brick.length() as u32 <= l.end
+                    && brick.w >= w.start && brick.w + brick.brick.width() as u32 <= w.end;
+                match is_inside {
+                    true => Some(PlacedBrick { l: brick.l - l.start, w: brick.w - w.start, ..brick }),
+                    false => None
+                }
+            }
+        )
+    }
+
+    pub fn paste(self, other: &Mosaic<U, B, C>, l: u32, w: u32) -> Self {
+        let length = self.length.max(other.length + l);
+        let width = self.width.max(other.width + w);
+
+        let pasted_points: BTreeSet<(u32, u32, u32)> = other.sections.iter()
+            .flat_map(|(section_l, section_w, section_h, chunks)| chunks.iter().flat_map(move |chunk|
+                chunk.points().map(move |(point_l, point_w, point_h)| (
+                    section_l + chunk.l as u32 + point_l as u32 + l,
+                    section_w + chunk.w as u32 + point_w as u32 + w,
+                    section_h + chunk.h as u32 + point_h as u32
+                ))
             ))
             .collect();
 
-        Ok(Mosaic::new(chunks, self.length, self.width))
+        /* Points of this mosaic under the pasted mosaic are removed. Bricks that are only partly
+           covered are replaced by unit bricks for their remaining points. */
+        let base = self.transform(
+            length,
+            width,
+            |point_l, point_w, h| match pasted_points.contains(&(point_l, point_w, h)) {
+                true => None,
+                false => Some((point_l, point_w, h))
+            },
+            |brick| {
+                let is_covered = (brick.l..(brick.l + brick.brick.length() as u32))
+                    .flat_map(|l| (brick.w..(brick.w + brick.brick.width() as u32)).map(move |w| (l, w)))
+                    .flat_map(|(l, w)| (brick.h..(brick.h + brick.brick.height() as u32)).map(move |h| (l, w, h)))
+                    .any(|point| pasted_points.contains(&point));
+                match is_covered {
+                    true => None,
+                    false => Some(brick)
+                }
+            }
+        );
+        let pasted = other.clone().translate(l, w);
+
+        let mut sections = base.sections;
+        for (section_l, section_w, section_h, mut chunks) in pasted.sections {
+            match sections.iter().position(|&(l, w, h, _)| (l, w, h) == (section_l, section_w, section_h)) {
+                Some(index) => sections[index].3.append(&mut chunks),
+                None => sections.push((section_l, section_w, section_h, chunks))
+            }
+        }
+
+        let mut warnings = base.warnings;
+        add_warnings(&mut warnings, pasted.warnings);
+        Mosaic::new(sections, length, width, warnings)
     }
 
     pub fn length(&self) -> u32 {
@@ -285,6 +771,80 @@ impl<U: UnitBrick, B: NonUnitBrick<U>, C: Color> Mosaic<U, B, C> {
         self.width
     }
 
+    pub fn warnings(&self) -> &[MosaicWarning<B>] {
+        &self.warnings
+    }
+
+    pub fn strength(&self) -> Vec<ChunkStrength> {
+        self.sections.iter().flat_map(|(l, w, h, chunks)|
+            chunks.iter().map(move |chunk| {
+                let (seams, aligned_seams) = chunk.count_seams();
+                ChunkStrength {
+                    l: l + chunk.l as u32,
+                    w: w + chunk.w as u32,
+                    h: h + chunk.h as u32,
+                    length: chunk.length,
+                    width: chunk.width,
+                    height: chunk.height,
+                    seams,
+                    aligned_seams
+                }
+            })
+        ).collect()
+    }
+
+    // Bricks are stored in their chunk and take its color, so only their unit brick can disagree with it
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        let mut cover_counts = BTreeMap::new();
+        let mut chunk_points = Vec::new();
+
+        for (section_l, section_w, section_h, chunks) in &self.sections {
+            for chunk in chunks {
+                let (chunk_l, chunk_w, chunk_h) = (section_l + chunk.l as u32, section_w + chunk.w as u32, section_h + chunk.h as u32);
+                let points: BTreeSet<(u32, u32, u32)> = chunk.points()
+                    .map(|(l, w, h)| (chunk_l + l as u32, chunk_w + w as u32, chunk_h + h as u32))
+                    .collect();
+
+                for brick in &chunk.bricks {
+                    let (l, w, h) = (chunk_l + brick.l as u32, chunk_w + brick.w as u32, chunk_h + brick.h as u32);
+                    let brick_points = points_in_box(l, w, h, brick.brick.length() as u32, brick.brick.width() as u32, brick.brick.height() as u32);
+
+                    if brick.brick.unit_brick() != chunk.unit_brick {
+                        issues.push(ValidationIssue::WrongUnitBrick(l, w, h));
+                    }
+
+                    if l + brick.brick.length() as u32 > self.length || w + brick.brick.width() as u32 > self.width {
+                        issues.push(ValidationIssue::OutOfBounds(l, w, h));
+                    }
+
+                    if brick_points.clone().any(|point| !points.contains(&point)) {
+                        issues.push(ValidationIssue::BrickOutsideChunk(l, w, h));
+                    }
+
+                    for point in brick_points {
+                        *cover_counts.entry(point).or_insert(0) += 1;
+                    }
+                }
+
+                chunk_points.extend(points);
+            }
+        }
+
+        for point in chunk_points {
+            if !cover_counts.contains_key(&point) {
+                issues.push(ValidationIssue::UncoveredPoint(point.0, point.1, point.2));
+            }
+        }
+
+        issues.extend(cover_counts.into_iter()
+            .filter(|&(_, count)| count > 1)
+            .map(|((l, w, h), _)| ValidationIssue::OverlappingBricks(l, w, h)));
+
+        issues.sort();
+        issues
+    }
+
     pub fn iter(&self) -> impl Iterator<Item=PlacedBrick<U, B, C>> + '_ {
         self.sections.iter().flat_map(|(l, w, h, chunks)|
             chunks.iter().flat_map(move |chunk|
@@ -299,21 +859,205 @@ impl<U: UnitBrick, B: NonUnitBrick<U>, C: Color> Mosaic<U, B, C> {
         )
     }
 
-    fn new(sections: Vec<Section<U, B, C>>, length: u32, width: u32) -> Self {
+    pub fn brick_at(&self, l: u32, w: u32, h: u32) -> Option<PlacedBrick<U, B, C>> {
+        self.iter_box(l..l.saturating_add(1), w..w.saturating_add(1), h..h.saturating_add(1)).next()
+    }
+
+    pub fn iter_layer(&self, h: u32) -> impl Iterator<Item=PlacedBrick<U, B, C>> + '_ {
+        self.iter_box(0..u32::MAX, 0..u32::MAX, h..h.saturating_add(1))
+    }
+
+    pub fn iter_region(&self, l: Range<u32>, w: Range<u32>) -> impl Iterator<Item=PlacedBrick<U, B, C>> + '_ {
+        self.iter_box(l, w, 0..u32::MAX)
+    }
+
+    pub fn top_color_at(&self, l: u32, w: u32) -> Option<C> {
+        self.iter_box(l..l.saturating_add(1), w..w.saturating_add(1), 0..u32::MAX)
+            .max_by_key(|brick| brick.h + brick.brick.height() as u32)
+            .map(|brick| brick.color)
+    }
+
+    fn iter_box(&self, l: Range<u32>, w: Range<u32>, h: Range<u32>) -> impl Iterator<Item=PlacedBrick<U, B, C>> + '_ {
+        let ranges = [(l.start, l.end), (w.start, w.end), (h.start, h.end)];
+        let overlaps = move |starts: [u32; 3], sizes: [u32; 3]| (0..3)
+            .all(|axis| starts[axis] < ranges[axis].1 && starts[axis] + sizes[axis] > ranges[axis].0);
+
+        self.chunks_in_box(l, w, h)
+            .flat_map(move |(section_l, section_w, section_h, chunk)| chunk.bricks.iter()
+                .map(move |brick| PlacedBrick {
+                    l: section_l + chunk.l as u32 + brick.l as u32,
+                    w: section_w + chunk.w as u32 + brick.w as u32,
+                    h: section_h + chunk.h as u32 + brick.h as u32,
+                    brick: brick.brick,
+                    color: chunk.color
+                })
+                .filter(move |brick| overlaps(
+                    [brick.l, brick.w, brick.h],
+                    [brick.brick.length() as u32, brick.brick.width() as u32, brick.brick.height() as u32]
+                ))
+            )
+    }
+
+    fn chunks_in_box(&self, l: Range<u32>, w: Range<u32>, h: Range<u32>) -> impl Iterator<Item=(u32, u32, u32, &Chunk<U, B, C>)> + '_ {
+        let ranges = [(l.start, l.end), (w.start, w.end), (h.start, h.end)];
+        let overlaps = move |starts: [u32; 3], sizes: [u32; 3]| (0..3)
+            .all(|axis| starts[axis] < ranges[axis].1 && starts[axis] + sizes[axis] > ranges[axis].0);
+
+        // Only chunks in the index's cells under the box are checked, and bricks are only looked at in chunks that overlap it
+        self.index.chunks_in(&l, &w).into_iter()
+            .map(move |(section_index, chunk_index)| {
+                let (section_l, section_w, section_h, chunks) = &self.sections[section_index];
+                (*section_l, *section_w, *section_h, &chunks[chunk_index])
+            })
+            .filter(move |&(section_l, section_w, section_h, chunk)| overlaps(
+                [section_l + chunk.l as u32, section_w + chunk.w as u32, section_h + chunk.h as u32],
+                [chunk.length as u32, chunk.width as u32, chunk.height as u32]
+            ))
+    }
+
+    fn region(&self, l: Range<u32>, w: Range<u32>) -> Self {
+        let mut sections: Vec<Section<U, B, C>> = Vec::new();
+
+        // Chunks only partly inside the region are kept whole, so callers should crop the result
+        for (section_l, section_w, section_h, chunk) in self.chunks_in_box(l, w, 0..u32::MAX) {
+            match sections.last_mut() {
+                Some((last_l, last_w, last_h, chunks)) if (*last_l, *last_w, *last_h) == (section_l, section_w, section_h) => chunks.push(chunk.clone()),
+                _ => sections.push((section_l, section_w, section_h, vec![chunk.clone()]))
+            }
+        }
+
+        Mosaic::new(sections, self.length, self.width, self.warnings.clone())
+    }
+
+    fn transform(self,
+                 length: u32,
+                 width: u32,
+                 point_fn: impl Fn(u32, u32, u32) -> Option<(u32, u32, u32)>,
+                 brick_fn: impl Fn(PlacedBrick<U, B, C>) -> Option<PlacedBrick<U, B, C>>) -> Self {
+        let section_size = u8::MAX as u32;
+        let mut chunks_by_section: BTreeMap<_, Vec<_>> = BTreeMap::new();
+
+        for (section_l, section_w, section_h, chunks) in self.sections {
+            for chunk in chunks {
+                let points = chunk.points()
+                    .filter_map(|(l, w, h)| point_fn(
+                        section_l + chunk.l as u32 + l as u32,
+                        section_w + chunk.w as u32 + w as u32,
+                        section_h + chunk.h as u32 + h as u32
+                    ))
+                    .collect();
+
+                let bricks = chunk.bricks.iter()
+                    .filter_map(|brick| brick_fn(PlacedBrick {
+                        l: section_l + chunk.l as u32 + brick.l as u32,
+                        w: section_w + chunk.w as u32 + brick.w as u32,
+                        h: section_h + chunk.h as u32 + brick.h as u32,
+                        brick: brick.brick,
+                        color: chunk.color
+                    }))
+                    .collect();
+
+                // Transformed points keep the chunk's size, so each new chunk still fits in a section
+                for ((l, w, h), new_chunk) in Chunk::from_points(chunk.unit_brick, chunk.color, points, bricks) {
+                    let origin = (l - l % section_size, w - w % section_size, h - h % section_size);
+                    chunks_by_section.entry(origin).or_default().push(Chunk {
+                        l: (l - origin.0) as u8,
+                        w: (w - origin.1) as u8,
+                        h: (h - origin.2) as u8,
+                        ..new_chunk
+                    });
+                }
+            }
+        }
+
+        let sections = chunks_by_section.into_iter()
+            .map(|((l, w, h), chunks)| (l, w, h, chunks))
+            .collect();
+        Mosaic::new(sections, length, width, self.warnings)
+    }
+
+    // The bricks are kept exactly as placed, so they must not overlap each other or this mosaic
+    fn add_bricks(self, unit_brick: U, color: C, bricks: Vec<PlacedBrick<U, B, C>>) -> Self {
+        let section_size = u8::MAX as u32;
+
+        /* Bricks are grouped by the block of half a section where they start, so each chunk made
+           from a group still fits in a section even with bricks reaching past the block's edge */
+        let block_size = section_size / 2;
+        let mut bricks_by_block: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for brick in bricks {
+            bricks_by_block.entry((brick.l / block_size, brick.w / block_size, brick.h / block_size)).or_default().push(brick);
+        }
+
+        let mut sections = self.sections;
+        for bricks in bricks_by_block.into_values() {
+            let points = bricks.iter()
+                .flat_map(|brick| points_in_box(
+                    brick.l,
+                    brick.w,
+                    brick.h,
+                    brick.brick.length() as u32,
+                    brick.brick.width() as u32,
+                    brick.brick.height() as u32
+                ))
+                .collect();
+
+            for ((l, w, h), chunk) in Chunk::from_points(unit_brick, color, points, bricks) {
+                let origin = (l - l % section_size, w - w % section_size, h - h % section_size);
+                let chunk = Chunk {
+                    l: (l - origin.0) as u8,
+                    w: (w - origin.1) as u8,
+                    h: (h - origin.2) as u8,
+                    ..chunk
+                };
+
+                match sections.iter().position(|&(l, w, h, _)| (l, w, h) == origin) {
+                    Some(index) => sections[index].3.push(chunk),
+                    None => sections.push((origin.0, origin.1, origin.2, vec![chunk]))
+                }
+            }
+        }
+
+        Mosaic::new(sections, self.length, self.width, self.warnings)
+    }
+
+    // Every mosaic is made here, so warnings from building a mosaic carry through every step after it
+    fn new(sections: Vec<Section<U, B, C>>, length: u32, width: u32, warnings: Vec<MosaicWarning<B>>) -> Self {
+        let sections: Vec<Section<U, B, C>> = sections.into_iter()
+            .filter(|(_, _, _, chunks)| chunks.iter().all(|chunk| chunk.length > 0 && chunk.width > 0 && chunk.height > 0))
+            .collect();
+
         Mosaic {
-            sections: sections.into_iter()
-                .filter(|(_, _, _, chunks)| chunks.iter().all(|chunk| chunk.length > 0 && chunk.width > 0 && chunk.height > 0))
-                .collect(),
+            index: ChunkIndex::new(&sections),
+            sections,
             length,
-            width
+            width,
+            warnings
         }
     }
 
-    fn make_sections<I: Image>(image: &I, section_size: u32) -> Vec<(u32, u32, u8, u8)> {
-        let mut section_l = 0;
+    fn brick_warnings(&self, bricks: &[B]) -> Vec<MosaicWarning<B>> {
+        let mut max_heights = BTreeMap::new();
+        for chunk in self.sections.iter().flat_map(|(_, _, _, chunks)| chunks) {
+            let max_height = max_heights.entry(chunk.unit_brick).or_insert(0);
+            *max_height = chunk.height.max(*max_height);
+        }
+
+        // Bricks of a unit brick the mosaic does not use are left alone, since they could not be placed anyway
+        let mut warnings = Vec::new();
+        add_warnings(&mut warnings, bricks.iter()
+            .filter_map(|&brick| match (brick.length(), brick.width(), brick.height()) {
+                (0, _, _) | (_, 0, _) | (_, _, 0) => Some(MosaicWarning::ZeroSizedBrick(brick)),
+                (_, _, height) => max_heights.get(&brick.unit_brick())
+                    .filter(|&&max_height| height > max_height)
+                    .map(|_| MosaicWarning::BrickTooTall(brick))
+            })
+            .collect());
+
+        warnings
+    }
 
-        let image_length = image.length();
-        let image_width = image.width();
+    fn make_sections(image_length: u32, image_width: u32, section_size: u32) -> Vec<(u32, u32, u8, u8)> {
+        let mut section_l = 0;
 
         let mut sections = Vec::new();
 
@@ -334,33 +1078,354 @@ impl<U: UnitBrick, B: NonUnitBrick<U>, C: Color> Mosaic<U, B, C> {
         sections
     }
 
-    fn build_chunks(length: u8,
-                    width: u8,
-                    max_height: u8,
-                    height_fn: impl Fn(u8, u8) -> u8,
-                    brick_fn: impl Fn(u8, u8, u8, C) -> U,
-                    color_fn: impl Fn(u8, u8) -> C) -> Result<Vec<Chunk<U, B, C>>, MosaicError> {
-        if max_height > 0 && usize::MAX / length as usize / width as usize / max_height as usize == 0 {
-            return Err(MosaicError::PointerTooSmall);
-        }
+    #[allow(clippy::too_many_arguments)]
+    fn build_section<I: Image>(image: &I,
+                               palette: &impl Palette<C>,
+                               height_fn: &impl Fn(u32, u32, C) -> u32,
+                               brick_fn: &impl Fn(u32, u32, u32, C) -> U,
+                               padding: u32,
+                               color_fn: &impl Fn(&Relief, usize, usize, u32, C) -> Option<C>,
+                               (section_l, section_w, section_length, section_width): (u32, u32, u8, u8),
+                               section_size: u32) -> Result<SectionsWithWarnings<U, B, C>, MosaicError> {
+        let mut sections = Vec::new();
+        let padded_length = section_length as u32 + 2 * padding;
+        let padded_width = section_width as u32 + 2 * padding;
+
+        /* Pad the section with the points around it so that whether a point can be seen does
+           not depend on where the section ends. Points outside the image have no height. */
+        let image_pos = |l: usize, w: usize| {
+            let image_l = (l as u32 + section_l).checked_sub(padding)?;
+            let image_w = (w as u32 + section_w).checked_sub(padding)?;
+            match image_l < image.length() && image_w < image.width() {
+                true => Some((image_l, image_w)),
+                false => None
+            }
+        };
+
+        // Cache colors and heights so they do not need to be recomputed for each (l, w)
+        let raw_colors: Pixels<RawColor> = Pixels::<RawColor>::from_fn(
+            |l, w| image_pos(l, w).map_or_else(RawColor::default, |(image_l, image_w)| image.pixel(image_l, image_w)),
+            padded_length as usize,
+            padded_width as usize
+        );
+        let (colors, defaulted) = raw_colors.with_palette(palette);
+
+        // Pixels in the padding belong to a neighboring section, which reports them itself
+        let padded_section_l = padding as usize..(padding + section_length as u32) as usize;
+        let padded_section_w = padding as usize..(padding + section_width as u32) as usize;
+        let mut defaulted_in_section = defaulted.into_iter()
+            .filter(|(l, w)| padded_section_l.contains(l) && padded_section_w.contains(w))
+            .filter_map(|(l, w)| image_pos(l, w));
+        let warnings = match defaulted_in_section.next() {
+            Some((first_l, first_w)) => vec![MosaicWarning::DefaultColor(defaulted_in_section.count() as u64 + 1, first_l, first_w)],
+            None => Vec::new()
+        };
+
+        let heights = HeightMap::from_fn(
+            |l, w| image_pos(l, w).map_or(0, |(image_l, image_w)| height_fn(image_l, image_w, colors.value(l, w))),
+            padded_length as usize,
+            padded_width as usize
+        );
+
+        // Find the lowest column near each point once instead of searching around every point in every layer
+        let near_heights = heights.window_min(padding as usize, padded_width as usize);
+
+        let relief = Relief {
+            l: section_l,
+            w: section_w,
+            padding,
+            heights,
+            near_heights
+        };
+
+        let padding = padding as usize;
+        let max_height = (0..section_width as usize)
+            .flat_map(|w| (0..section_length as usize).map(move |l| (l, w)))
+            .map(|(l, w)| relief.height(l + padding, w + padding))
+            .max()
+            .unwrap_or(0);
+
+        let mut section_h = 0;
+
+        while section_h < max_height {
+            let section_height = section_size.min(max_height - section_h);
+
+            // Build contiguous 3D chunks (with same color and brick) of the mosaic
+            let chunks = Mosaic::<U, B, C>::build_chunks(
+                section_length,
+                section_width,
+                section_height as u8,
+                |l, w| {
+                    let height = relief.height(l as usize + padding, w as usize + padding);
+                    match height > section_h {
+                        true => section_size.min(height - section_h) as u8,
+                        false => 0
+                    }
+                },
+                |l, w, h| {
+                    let padded_l = l as usize + padding;
+                    let padded_w = w as usize + padding;
+                    let global_h = h as u32 + section_h;
 
-        let mut visited = BoolVec::filled_with(length as usize * width as usize * max_height as usize, false);
-        let mut coords_to_visit = VecDeque::new();
-        let mut chunks = Vec::new();
+                    if global_h >= relief.height(padded_l, padded_w) {
+                        return None;
+                    }
 
-        /* An iterative breadth-first search that explores contiguous chunks of the mosaic with
-           the same brick type and color, similar to the classic island-finding problem */
-        for start_w in 0..width {
-            for start_l in 0..length {
-                let start_height = height_fn(start_l, start_w);
+                    let color = color_fn(&relief, padded_l, padded_w, global_h, colors.value(padded_l, padded_w))?;
+                    let brick = brick_fn(l as u32 + section_l, w as u32 + section_w, global_h, color);
+                    Some((brick, color))
+                }
+            )?;
+
+            sections.push((section_l, section_w, section_h, chunks));
+
+            section_h += section_height;
+        }
+
+        Ok((sections, warnings))
+    }
+
+    fn merge_across_sections(sections: Vec<Section<U, B, C>>, section_size: u32) -> Vec<Section<U, B, C>> {
+        let mut merged_sections: Vec<Section<U, B, C>> = sections.iter()
+            .map(|&(l, w, h, _)| (l, w, h, Vec::new()))
+            .collect();
+        let chunks: Vec<SectionChunk<U, B, C>> = sections.into_iter()
+            .flat_map(|(l, w, h, chunks)| chunks.into_iter().map(move |chunk| (l, w, h, chunk)))
+            .collect();
+
+        // Points on the east and south edges of each section, keyed by their global position and layers
+        let mut far_edges = BTreeMap::new();
+        let mut near_edges = Vec::new();
+
+        for (index, (section_l, section_w, section_h, chunk)) in chunks.iter().enumerate() {
+            let min_l = section_l + chunk.l as u32;
+            let min_w = section_w + chunk.w as u32;
+            let max_l = min_l + chunk.length as u32 - 1;
+            let max_w = min_w + chunk.width as u32 - 1;
+            let h = section_h + chunk.h as u32;
+
+            /* A chunk never crosses a section before merging, so only its outermost rows and
+               columns can touch a point in another section */
+            for rel_w in 0..chunk.width {
+                let w = min_w + rel_w as u32;
+
+                if (max_l + 1).is_multiple_of(section_size) && chunk.ws_included[chunk.length as usize - 1].contains(rel_w) {
+                    far_edges.insert((max_l, w, h, chunk.height), index);
+                }
+
+                if min_l > 0 && min_l.is_multiple_of(section_size) && chunk.ws_included[0].contains(rel_w) {
+                    near_edges.push((min_l - 1, w, h, chunk.height, index));
+                }
+            }
+
+            for (rel_l, ws_included) in chunk.ws_included.iter().enumerate() {
+                let l = min_l + rel_l as u32;
+
+                if (max_w + 1).is_multiple_of(section_size) && ws_included.contains(chunk.width - 1) {
+                    far_edges.insert((l, max_w, h, chunk.height), index);
+                }
+
+                if min_w > 0 && min_w.is_multiple_of(section_size) && ws_included.contains(0) {
+                    near_edges.push((l, min_w - 1, h, chunk.height, index));
+                }
+            }
+        }
+
+        let mut groups = Groups::new(chunks.len());
+        let mut bounds: Vec<(u32, u32, u32, u32)> = chunks.iter()
+            .map(|(section_l, section_w, _, chunk)| {
+                let min_l = section_l + chunk.l as u32;
+                let min_w = section_w + chunk.w as u32;
+                (min_l, min_w, min_l + chunk.length as u32 - 1, min_w + chunk.width as u32 - 1)
+            })
+            .collect();
+
+        for (l, w, h, height, index) in near_edges {
+            let other = match far_edges.get(&(l, w, h, height)) {
+                Some(&other) => other,
+                None => continue
+            };
+
+            let chunk = &chunks[index].3;
+            let other_chunk = &chunks[other].3;
+            if chunk.unit_brick != other_chunk.unit_brick || chunk.color != other_chunk.color {
+                continue;
+            }
+
+            let root = groups.root(index);
+            let other_root = groups.root(other);
+            if root == other_root {
+                continue;
+            }
+
+            let (min_l, min_w, max_l, max_w) = bounds[root];
+            let (other_min_l, other_min_w, other_max_l, other_max_w) = bounds[other_root];
+            let (new_root, child) = (root.min(other_root), root.max(other_root));
+            groups.join(new_root, child);
+            bounds[new_root] = (min_l.min(other_min_l), min_w.min(other_min_w), max_l.max(other_max_l), max_w.max(other_max_w));
+        }
+
+        let roots: Vec<usize> = (0..chunks.len()).map(|index| groups.root(index)).collect();
+        let mut group_sizes: BTreeMap<usize, usize> = BTreeMap::new();
+        for &root in &roots {
+            *group_sizes.entry(root).or_insert(0) += 1;
+        }
+
+        let mut members: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for (index, &root) in roots.iter().enumerate() {
+            if group_sizes[&root] > 1 {
+                members.entry(root).or_default().push(index);
+            }
+        }
+
+        let mut pieces_by_root: BTreeMap<usize, Vec<SectionChunk<U, B, C>>> = members.iter()
+            .map(|(&root, group)| (root, Mosaic::cut_group(&chunks, group, bounds[root], section_size)))
+            .collect();
+
+        let mut section_indices: BTreeMap<(u32, u32, u32), usize> = merged_sections.iter()
+            .enumerate()
+            .map(|(index, &(l, w, h, _))| ((l, w, h), index))
+            .collect();
+
+        for (index, (section_l, section_w, section_h, chunk)) in chunks.into_iter().enumerate() {
+            let root = roots[index];
+
+            if group_sizes[&root] == 1 {
+                merged_sections[section_indices[&(section_l, section_w, section_h)]].3.push(chunk);
+                continue;
+            }
+
+            // The merged chunks replace the group's first member, and the other members are dropped
+            let pieces = match pieces_by_root.remove(&root) {
+                Some(pieces) => pieces,
+                None => continue
+            };
+
+            for (target_l, target_w, target_h, piece) in pieces {
+                let section_index = *section_indices.entry((target_l, target_w, target_h)).or_insert_with(|| {
+                    merged_sections.push((target_l, target_w, target_h, Vec::new()));
+                    merged_sections.len() - 1
+                });
+
+                merged_sections[section_index].3.push(piece);
+            }
+        }
+
+        merged_sections.retain(|(_, _, _, chunks)| !chunks.is_empty());
+        merged_sections
+    }
+
+    /* Chunks use u8s for their coordinates, so a group longer or wider than a section is cut into
+       pieces that fit. Cutting into one more piece than needed leaves room to shorten the first
+       piece, so a region that starts on a section edge is not cut on the next one. The shift
+       depends on the layer the group starts on, so groups stacked on each other are not cut in
+       the same place. Each piece is returned with the position of the section it belongs to. */
+    fn cut_group(chunks: &[SectionChunk<U, B, C>], group: &[usize], (min_l, min_w, max_l, max_w): (u32, u32, u32, u32),
+                 section_size: u32) -> Vec<SectionChunk<U, B, C>> {
+        let (_, _, first_h, first_chunk) = &chunks[group[0]];
+        let layer = first_h + first_chunk.h as u32;
+        let piece_of = |coord: u32, min: u32, max: u32| {
+            let extent = max - min + 1;
+            if extent <= section_size {
+                return 0;
+            }
+
+            let slack = (extent / section_size + 1) * section_size - extent;
+            let shift = slack * (1 + layer % 2) / 3;
+            (coord - min + shift) / section_size
+        };
+
+        // Each member's rows are read in place, so the group's points are never gathered in one set
+        let member_points = |index: usize| {
+            let (section_l, section_w, _, chunk) = &chunks[index];
+            let chunk_l = section_l + chunk.l as u32;
+            let chunk_w = section_w + chunk.w as u32;
+
+            chunk.ws_included.iter().enumerate().flat_map(move |(rel_l, ws_included)| (0..chunk.width)
+                .filter(move |&rel_w| ws_included.contains(rel_w))
+                .map(move |rel_w| (chunk_l + rel_l as u32, chunk_w + rel_w as u32)))
+        };
+
+        let mut piece_bounds: BTreeMap<(u32, u32), (u32, u32, u32, u32)> = BTreeMap::new();
+        for (l, w) in group.iter().flat_map(|&index| member_points(index)) {
+            let (piece_min_l, piece_min_w, piece_max_l, piece_max_w) = piece_bounds
+                .entry((piece_of(l, min_l, max_l), piece_of(w, min_w, max_w)))
+                .or_insert((l, w, l, w));
+            *piece_min_l = (*piece_min_l).min(l);
+            *piece_min_w = (*piece_min_w).min(w);
+            *piece_max_l = (*piece_max_l).max(l);
+            *piece_max_w = (*piece_max_w).max(w);
+        }
+
+        let mut ws_by_piece: BTreeMap<(u32, u32), Vec<WSet>> = piece_bounds.iter()
+            .map(|(&piece, &(piece_min_l, _, piece_max_l, _))| (piece, vec![WSet::default(); (piece_max_l - piece_min_l + 1) as usize]))
+            .collect();
+        for (l, w) in group.iter().flat_map(|&index| member_points(index)) {
+            let piece = (piece_of(l, min_l, max_l), piece_of(w, min_w, max_w));
+            let (piece_min_l, piece_min_w, _, _) = piece_bounds[&piece];
+            ws_by_piece.get_mut(&piece).unwrap()[(l - piece_min_l) as usize].insert((w - piece_min_w) as u8);
+        }
+
+        ws_by_piece.into_iter().map(|(piece, ws_included)| {
+            let (piece_min_l, piece_min_w, piece_max_l, piece_max_w) = piece_bounds[&piece];
+            let width = (piece_max_w - piece_min_w + 1) as u8;
+
+            let mut bricks = Vec::new();
+            for (rel_l, row) in ws_included.iter().enumerate() {
+                for rel_w in (0..width).filter(|&rel_w| row.contains(rel_w)) {
+                    for rel_h in 0..first_chunk.height {
+                        bricks.push(ChunkPlacedBrick { l: rel_l as u8, w: rel_w, h: rel_h, brick: Brick::Unit(first_chunk.unit_brick) });
+                    }
+                }
+            }
+
+            // Store each piece in the section that contains its corner
+            let target_l = piece_min_l - piece_min_l % section_size;
+            let target_w = piece_min_w - piece_min_w % section_size;
+
+            (target_l, target_w, *first_h, Chunk {
+                unit_brick: first_chunk.unit_brick,
+                color: first_chunk.color,
+                l: (piece_min_l - target_l) as u8,
+                w: (piece_min_w - target_w) as u8,
+                h: first_chunk.h,
+                length: (piece_max_l - piece_min_l + 1) as u8,
+                width,
+                height: first_chunk.height,
+                ws_included,
+                bricks
+            })
+        }).collect()
+    }
+
+    fn build_chunks(length: u8,
+                    width: u8,
+                    max_height: u8,
+                    height_fn: impl Fn(u8, u8) -> u8,
+                    voxel_fn: impl Fn(u8, u8, u8) -> Option<(U, C)>) -> Result<Vec<Chunk<U, B, C>>, MosaicError> {
+        if max_height > 0 && usize::MAX / length as usize / width as usize / max_height as usize == 0 {
+            return Err(MosaicError::PointerTooSmall);
+        }
+
+        let mut visited = BoolVec::filled_with(length as usize * width as usize * max_height as usize, false);
+        let mut coords_to_visit = VecDeque::new();
+        let mut chunks = Vec::new();
+
+        /* An iterative breadth-first search that explores contiguous chunks of the mosaic with
+           the same brick type and color, similar to the classic island-finding problem */
+        for start_w in 0..width {
+            for start_l in 0..length {
+                let start_height = height_fn(start_l, start_w);
 
                 for start_h in 0..start_height {
                     if was_visited(&visited, start_l, start_w, start_h, length, width) {
                         continue;
                     }
 
-                    let start_color = color_fn(start_l, start_w);
-                    let start_brick = brick_fn(start_l, start_w, start_h, start_color);
+                    let (start_brick, start_color) = match voxel_fn(start_l, start_w, start_h) {
+                        Some(voxel) => voxel,
+                        None => continue
+                    };
                     coords_to_visit.push_back((start_l, start_w, start_h));
 
                     let mut coords_in_chunk = BTreeMap::new();
@@ -390,35 +1455,35 @@ impl<U: UnitBrick, B: NonUnitBrick<U>, C: Color> Mosaic<U, B, C> {
 
                         // Add position to the west to explore later
                         if l > 0 && height_fn(l - 1, w) > h
-                            && is_new_pos::<U, C>(&visited, &brick_fn, &color_fn, l - 1, w, h, length, width, start_brick, start_color) {
+                            && is_new_pos::<U, C>(&visited, &voxel_fn, l - 1, w, h, length, width, start_brick, start_color) {
                             coords_to_visit.push_back((l - 1, w, h));
                         }
 
                         // Add position to the east to explore later
                         if l < length - 1 && height_fn(l + 1, w) > h
-                            && is_new_pos::<U, C>(&visited, &brick_fn, &color_fn, l + 1, w, h, length, width, start_brick, start_color) {
+                            && is_new_pos::<U, C>(&visited, &voxel_fn, l + 1, w, h, length, width, start_brick, start_color) {
                             coords_to_visit.push_back((l + 1, w, h));
                         }
 
                         // Add position to the south to explore later
                         if w > 0 && height_fn(l, w - 1) > h
-                            && is_new_pos::<U, C>(&visited, &brick_fn, &color_fn, l, w - 1, h, length, width, start_brick, start_color) {
+                            && is_new_pos::<U, C>(&visited, &voxel_fn, l, w - 1, h, length, width, start_brick, start_color) {
                             coords_to_visit.push_back((l, w - 1, h));
                         }
 
                         // Add position to the north to explore later
                         if w < width - 1 && height_fn(l, w + 1) > h
-                            && is_new_pos::<U, C>(&visited, &brick_fn, &color_fn, l, w + 1, h, length, width, start_brick, start_color) {
+                            && is_new_pos::<U, C>(&visited, &voxel_fn, l, w + 1, h, length, width, start_brick, start_color) {
                             coords_to_visit.push_back((l, w + 1, h));
                         }
 
                         // Add position below to explore later
-                        if h > 0 && is_new_pos::<U, C>(&visited, &brick_fn, &color_fn, l, w, h - 1, length, width, start_brick, start_color) {
+                        if h > 0 && is_new_pos::<U, C>(&visited, &voxel_fn, l, w, h - 1, length, width, start_brick, start_color) {
                             coords_to_visit.push_back((l, w, h - 1));
                         }
 
                         // Add position above to explore later
-                        if h < height - 1 && is_new_pos::<U, C>(&visited, &brick_fn, &color_fn, l, w, h + 1, length, width, start_brick, start_color) {
+                        if h < height - 1 && is_new_pos::<U, C>(&visited, &voxel_fn, l, w, h + 1, length, width, start_brick, start_color) {
                             coords_to_visit.push_back((l, w, h + 1));
                         }
                     }
@@ -481,7 +1546,7 @@ impl<U: UnitBrick, B: NonUnitBrick<U>, C: Color> Mosaic<U, B, C> {
         for height in heights {
             let coords_in_slice = &coords_in_chunk[&slice_h];
             let mut bricks = Vec::with_capacity(coords_in_slice.len());
-            let mut ws_included = vec![BTreeSet::new(); chunk_length as usize];
+            let mut ws_included = vec![WSet::default(); chunk_length as usize];
 
             for &(l, w) in coords_in_slice {
                 let rel_l = l - min_l;
@@ -521,7 +1586,12 @@ impl<U: UnitBrick, B: NonUnitBrick<U>, C: Color> Mosaic<U, B, C> {
 // PRIVATE TYPE ALIASES
 // ====================
 
+/* A section's position and its chunks, whose coordinates are relative to that position. Chunks
+   merged across sections are kept in the section holding their corner, so a chunk may extend past
+   its section's bounds, though never by more than its own u8 length and width. */
 type Section<U, B, C> = (u32, u32, u32, Vec<Chunk<U, B, C>>);
+type SectionChunk<U, B, C> = (u32, u32, u32, Chunk<U, B, C>);
+type SectionsWithWarnings<U, B, C> = (Vec<Section<U, B, C>>, Vec<MosaicWarning<B>>);
 type HeightMap = Pixels<u32>;
 
 // ====================
@@ -538,8 +1608,7 @@ fn was_visited(visited: &BoolVec, l: u8, w: u8, h: u8, length: u8, width: u8) ->
 
 #[allow(clippy::too_many_arguments)]
 fn is_new_pos<U: UnitBrick, C: Color>(visited: &BoolVec,
-                                      brick_fn: impl Fn(u8, u8, u8, C) -> U,
-                                      color_fn: impl Fn(u8, u8) -> C,
+                                      voxel_fn: impl Fn(u8, u8, u8) -> Option<(U, C)>,
                                       l: u8,
                                       w: u8,
                                       h: u8,
@@ -547,7 +1616,72 @@ fn is_new_pos<U: UnitBrick, C: Color>(visited: &BoolVec,
                                       width: u8,
                                       start_brick: U,
                                       start_color: C) -> bool {
-    !was_visited(visited, l, w, h, length, width) && brick_fn(l, w, h, start_color) == start_brick && color_fn(l, w) == start_color
+    !was_visited(visited, l, w, h, length, width) && voxel_fn(l, w, h) == Some((start_brick, start_color))
+}
+
+/* Warnings are only gathered from a few sources, and bricks can only be compared for equality, so
+   a short list is searched for repeats. Pixels with the default color are counted together, since
+   an empty palette would otherwise give one warning for every pixel. */
+fn add_warnings<B: Eq>(warnings: &mut Vec<MosaicWarning<B>>, new_warnings: Vec<MosaicWarning<B>>) {
+    for warning in new_warnings {
+        let default_color = warnings.iter_mut().find(|warning| matches!(warning, MosaicWarning::DefaultColor(..)));
+        match (warning, default_color) {
+            (MosaicWarning::DefaultColor(count, l, w), Some(MosaicWarning::DefaultColor(total, first_l, first_w))) => {
+                *total += count;
+                if (w, l) < (*first_w, *first_l) {
+                    (*first_l, *first_w) = (l, w);
+                }
+            },
+            (warning, _) => if !warnings.contains(&warning) {
+                warnings.push(warning);
+            }
+        }
+    }
+}
+
+// Minimum of the values within the radius of each index, using a queue of values that could still be a minimum
+fn window_min<T: Copy + Ord>(len: usize, radius: usize, value: impl Fn(usize) -> T) -> Vec<T> {
+    let mut mins = Vec::with_capacity(len);
+    let mut candidates: VecDeque<(usize, T)> = VecDeque::new();
+    let mut next = 0;
+
+    for index in 0..len {
+        while next < len && next <= index + radius {
+            let next_value = value(next);
+
+            // A value is never the minimum again once a smaller or equal value enters after it
+            while candidates.back().is_some_and(|&(_, back_value)| back_value >= next_value) {
+                candidates.pop_back();
+            }
+
+            candidates.push_back((next, next_value));
+            next += 1;
+        }
+
+        while candidates.front().is_some_and(|&(front, _)| front + radius < index) {
+            candidates.pop_front();
+        }
+
+        mins.push(candidates[0].1);
+    }
+
+    mins
+}
+
+fn points_in_box(l: u32, w: u32, h: u32, length: u32, width: u32, height: u32) -> impl Iterator<Item=(u32, u32, u32)> + Clone {
+    (h..(h + height)).flat_map(move |h| (w..(w + width)).flat_map(move |w| (l..(l + length)).map(move |l| (l, w, h))))
+}
+
+/* Items are mapped on a thread pool when the parallel feature is enabled. The results keep the
+   order of the items either way, so the output does not depend on how the work was scheduled. */
+#[cfg(feature = "parallel")]
+fn map_in_order<T: ThreadSafe, R: ThreadSafe>(items: Vec<T>, f: impl Fn(T) -> R + ThreadSafe) -> Vec<R> {
+    items.into_par_iter().map(f).collect()
+}
+
+#[cfg(not(feature = "parallel"))]
+fn map_in_order<T, R>(items: Vec<T>, f: impl Fn(T) -> R) -> Vec<R> {
+    items.into_iter().map(f).collect()
 }
 
 // ====================
@@ -620,16 +1754,17 @@ struct Chunk<U, B, C> {
     length: u8,
     width: u8,
     height: u8,
-    ws_included: Vec<BTreeSet<u8>>,
+    ws_included: Vec<WSet>,
     bricks: Vec<ChunkPlacedBrick<U, B>>
 }
 
 impl<U: UnitBrick, B: NonUnitBrick<U>, C: Color> Chunk<U, B, C> {
 
-    fn reduce_bricks(self, sizes: &[VolumeSortedBrick<U, B>]) -> Self {
+    fn reduce_bricks(self, sizes: &[VolumeSortedBrick<U, B>], seam_penalty: u32) -> Self {
         let mut ws_included_by_h: Vec<_> = (0..self.height)
             .map(|_| self.ws_included.clone())
             .collect();
+        let mut owners = vec![None; self.length as usize * self.width as usize * self.height as usize];
         let mut bricks = Vec::new();
 
         /* For every space in the chunk that is empty, try to fit the largest possible brick in
@@ -647,18 +1782,43 @@ impl<U: UnitBrick, B: NonUnitBrick<U>, C: Color> Chunk<U, B, C> {
                 while !ws_included_by_h[h_index][l_index].is_empty() {
                     let ws_included = &ws_included_by_h[h_index][l_index];
 
-                    if let Some(&w) = ws_included.first() {
+                    if let Some(w) = ws_included.first() {
+                        let mut best_size = None;
+                        let mut best_score = i64::MIN;
+
                         for size in sizes {
                             if Chunk::<U, B, C>::fits(l, w, h, size.length(), size.width(), size.height(), &ws_included_by_h) {
-                                Chunk::<U, B, C>::remove_brick(l, w, h, size.length(), size.width(), size.height(), &mut ws_included_by_h);
-                                bricks.push(ChunkPlacedBrick {
-                                    l,
-                                    w,
-                                    h,
-                                    brick: size.brick
-                                });
+
+                                // Without a penalty, the largest brick that fits is always the best
+                                if seam_penalty == 0 {
+                                    best_size = Some(size);
+                                    break;
+                                }
+
+                                /* Each edge of the brick that lines up with a seam in the layer below costs
+                                   seam_penalty units of volume, trading extra bricks for a running bond. */
+                                let aligned_seams = self.count_aligned_edges(l, w, h, size.length(), size.width(), &owners);
+                                let score = size.volume() as i64 - seam_penalty as i64 * aligned_seams as i64;
+
+                                // Ties go to the larger brick because sizes are sorted by volume
+                                if score > best_score {
+                                    best_size = Some(size);
+                                    best_score = score;
+                                }
+
                             }
                         }
+
+                        if let Some(size) = best_size {
+                            Chunk::<U, B, C>::remove_brick(l, w, h, size.length(), size.width(), size.height(), &mut ws_included_by_h);
+                            self.set_owner(l, w, h, size.length(), size.width(), size.height(), bricks.len(), &mut owners);
+                            bricks.push(ChunkPlacedBrick {
+                                l,
+                                w,
+                                h,
+                                brick: size.brick
+                            });
+                        }
                     }
 
                 }
@@ -679,7 +1839,181 @@ impl<U: UnitBrick, B: NonUnitBrick<U>, C: Color> Chunk<U, B, C> {
         }
     }
 
-    fn fits(l: u8, w: u8, h: u8, length: u8, width: u8, height: u8, ws_included_by_h: &[Vec<BTreeSet<u8>>]) -> bool {
+    fn from_points(unit_brick: U,
+                   color: C,
+                   points: BTreeSet<(u32, u32, u32)>,
+                   bricks: Vec<PlacedBrick<U, B, C>>) -> Vec<((u32, u32, u32), Self)> {
+        let mut footprints: BTreeMap<u32, BTreeSet<(u32, u32)>> = BTreeMap::new();
+        for &(l, w, h) in &points {
+            footprints.entry(h).or_default().insert((l, w));
+        }
+
+        // Chunks must have the same footprint in every layer, so split runs of layers where it changes
+        let mut runs: Vec<(u32, u32, &BTreeSet<_>)> = Vec::new();
+        for (&h, footprint) in &footprints {
+            match runs.last_mut() {
+                Some((start_h, height, last_footprint)) if *start_h + *height == h && *last_footprint == footprint => *height += 1,
+                _ => runs.push((h, 1, footprint))
+            }
+        }
+        let run_index = |h: u32| {
+            let index = runs.partition_point(|&(start_h, _, _)| start_h <= h).checked_sub(1)?;
+            let (start_h, height, _) = runs[index];
+            match h < start_h + height {
+                true => Some(index),
+                false => None
+            }
+        };
+
+        let mut bricks_by_run: Vec<Vec<PlacedBrick<U, B, C>>> = vec![Vec::new(); runs.len()];
+        let mut covered = BTreeSet::new();
+        for brick in bricks {
+            let brick_points: Vec<(u32, u32, u32)> = (brick.h..(brick.h + brick.brick.height() as u32))
+                .flat_map(|h| (brick.w..(brick.w + brick.brick.width() as u32)).map(move |w| (w, h)))
+                .flat_map(|(w, h)| (brick.l..(brick.l + brick.brick.length() as u32)).map(move |l| (l, w, h)))
+                .collect();
+
+            // A brick that lost any of its points or spans two runs is replaced by unit bricks below
+            let top_h = brick.h + brick.brick.height() as u32 - 1;
+            let is_whole = brick_points.iter().all(|point| points.contains(point));
+            if let (true, Some(index)) = (is_whole, run_index(brick.h).filter(|&index| Some(index) == run_index(top_h))) {
+                covered.extend(brick_points);
+                bricks_by_run[index].push(brick);
+            }
+        }
+
+        for &(l, w, h) in points.difference(&covered) {
+            bricks_by_run[run_index(h).unwrap()].push(PlacedBrick { l, w, h, brick: Brick::Unit(unit_brick), color });
+        }
+
+        runs.iter().zip(bricks_by_run).map(|(&(start_h, height, footprint), bricks)| {
+            let min_l = footprint.iter().map(|&(l, _)| l).min().unwrap();
+            let max_l = footprint.iter().map(|&(l, _)| l).max().unwrap();
+            let min_w = footprint.iter().map(|&(_, w)| w).min().unwrap();
+            let max_w = footprint.iter().map(|&(_, w)| w).max().unwrap();
+
+            let mut ws_included = vec![WSet::default(); (max_l - min_l + 1) as usize];
+            for &(l, w) in footprint {
+                ws_included[(l - min_l) as usize].insert((w - min_w) as u8);
+            }
+
+            let chunk = Chunk {
+                unit_brick,
+                color,
+                l: 0,
+                w: 0,
+                h: 0,
+                length: (max_l - min_l + 1) as u8,
+                width: (max_w - min_w + 1) as u8,
+                height: height as u8,
+                ws_included,
+                bricks: bricks.into_iter().map(|brick| ChunkPlacedBrick {
+                    l: (brick.l - min_l) as u8,
+                    w: (brick.w - min_w) as u8,
+                    h: (brick.h - start_h) as u8,
+                    brick: brick.brick
+                }).collect()
+            };
+
+            ((min_l, min_w, start_h), chunk)
+        }).collect()
+    }
+
+    fn points(&self) -> impl Iterator<Item=(u8, u8, u8)> + '_ {
+        (0..self.height).flat_map(move |h|
+            self.ws_included.iter().enumerate().flat_map(move |(l, ws_included)|
+                (0..self.width).filter(|&w| ws_included.contains(w)).map(move |w| (l as u8, w, h))
+            )
+        )
+    }
+
+    fn count_seams(&self) -> (u32, u32) {
+        let mut owners = vec![None; self.length as usize * self.width as usize * self.height as usize];
+        for (index, brick) in self.bricks.iter().enumerate() {
+            self.set_owner(brick.l, brick.w, brick.h, brick.brick.length(), brick.brick.width(), brick.brick.height(), index, &mut owners);
+        }
+
+        let mut seams = 0;
+        let mut aligned_seams = 0;
+
+        // The bottom layer has nothing below it in the chunk, so its seams cannot be bridged here
+        for h in 1..self.height {
+            for w in 0..self.width {
+                for l in 0..self.length {
+                    let east_neighbor = (l + 1 < self.length).then(|| (l + 1, w));
+                    let south_neighbor = (w + 1 < self.width).then(|| (l, w + 1));
+
+                    for (other_l, other_w) in east_neighbor.into_iter().chain(south_neighbor) {
+                        let owner = owners[self.owner_index(l, w, h)];
+                        let other_owner = owners[self.owner_index(other_l, other_w, h)];
+
+                        if owner.is_none() || other_owner.is_none() || owner == other_owner {
+                            continue;
+                        }
+
+                        seams += 1;
+                        if owners[self.owner_index(l, w, h - 1)] != owners[self.owner_index(other_l, other_w, h - 1)] {
+                            aligned_seams += 1;
+                        }
+                    }
+                }
+            }
+        }
+
+        (seams, aligned_seams)
+    }
+
+    fn count_aligned_edges(&self, l: u8, w: u8, h: u8, length: u8, width: u8, owners: &[Option<usize>]) -> u32 {
+        if h == 0 {
+            return 0;
+        }
+
+        let max_l = l + length;
+        let max_w = w + width;
+        let mut edges = Vec::with_capacity(2 * (length as usize + width as usize));
+
+        // Pair each point on the brick's edge with the point just outside the brick
+        for cur_w in w..max_w {
+            if l > 0 {
+                edges.push(((l, cur_w), (l - 1, cur_w)));
+            }
+            if max_l < self.length {
+                edges.push(((max_l - 1, cur_w), (max_l, cur_w)));
+            }
+        }
+        for cur_l in l..max_l {
+            if w > 0 {
+                edges.push(((cur_l, w), (cur_l, w - 1)));
+            }
+            if max_w < self.width {
+                edges.push(((cur_l, max_w - 1), (cur_l, max_w)));
+            }
+        }
+
+        edges.into_iter()
+            .filter(|&(_, (outside_l, outside_w))| self.ws_included[outside_l as usize].contains(outside_w))
+            .filter(|&((inside_l, inside_w), (outside_l, outside_w))|
+                owners[self.owner_index(inside_l, inside_w, h - 1)] != owners[self.owner_index(outside_l, outside_w, h - 1)]
+            )
+            .count() as u32
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn set_owner(&self, l: u8, w: u8, h: u8, length: u8, width: u8, height: u8, owner: usize, owners: &mut [Option<usize>]) {
+        for cur_h in h..(h + height) {
+            for cur_w in w..(w + width) {
+                for cur_l in l..(l + length) {
+                    owners[self.owner_index(cur_l, cur_w, cur_h)] = Some(owner);
+                }
+            }
+        }
+    }
+
+    fn owner_index(&self, l: u8, w: u8, h: u8) -> usize {
+        (h as usize * self.width as usize + w as usize) * self.length as usize + l as usize
+    }
+
+    fn fits(l: u8, w: u8, h: u8, length: u8, width: u8, height: u8, ws_included_by_h: &[Vec<WSet>]) -> bool {
         if u8::MAX - height < h || u8::MAX - length < l || u8::MAX - width < w {
             return false;
         }
@@ -700,7 +2034,7 @@ impl<U: UnitBrick, B: NonUnitBrick<U>, C: Color> Chunk<U, B, C> {
         true
     }
 
-    fn fits_layer(l: u8, w: u8, length: u8, width: u8, ws_included_by_l: &[BTreeSet<u8>]) -> bool {
+    fn fits_layer(l: u8, w: u8, length: u8, width: u8, ws_included_by_l: &[WSet]) -> bool {
         let max_l = l + length;
         let max_w = w + width;
 
@@ -710,108 +2044,346 @@ impl<U: UnitBrick, B: NonUnitBrick<U>, C: Color> Chunk<U, B, C> {
         }
 
         // Check whether every point in the chunk that would be filled by the brick is empty
-        for test_l in l..max_l {
-            if ws_included_by_l[test_l as usize].range(w..max_w).count() < width as usize {
-                return false;
-            }
-        }
-
-        true
+        ws_included_by_l[l as usize..max_l as usize].iter().all(|ws_included| ws_included.contains_range(w, max_w))
     }
 
-    fn remove_brick(l: u8, w: u8, h: u8, length: u8, width: u8, height: u8, ws_included_by_h: &mut [Vec<BTreeSet<u8>>]) {
+    fn remove_brick(l: u8, w: u8, h: u8, length: u8, width: u8, height: u8, ws_included_by_h: &mut [Vec<WSet>]) {
         let max_h = h + height;
         for h_index in h..max_h {
             Chunk::<U, B, C>::remove_brick_layer(l, w, length, width, &mut ws_included_by_h[h_index as usize]);
         }
     }
 
-    fn remove_brick_layer(l: u8, w: u8, length: u8, width: u8, ws_included_by_l: &mut [BTreeSet<u8>]) {
+    fn remove_brick_layer(l: u8, w: u8, length: u8, width: u8, ws_included_by_l: &mut [WSet]) {
         let min_l = l as usize;
         let max_w = w + width;
 
         // Remove all entries corresponding to a point inside the brick
         for ws_included in ws_included_by_l.iter_mut().skip(min_l).take(length as usize) {
-            for cur_w in w..max_w {
-                ws_included.remove(&cur_w);
-            }
+            ws_included.remove_range(w, max_w);
         }
 
     }
 }
 
-struct Pixels<T> {
-    values_by_row: Vec<T>,
-    length: usize
+/* A set of w coordinates packed into bits. Chunks are at most 255 points wide, so four words cover
+   every w in a chunk. Checking or clearing the points under a brick then only takes a few mask
+   operations per row instead of one tree lookup per point. */
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+struct WSet {
+    words: [u64; 4]
 }
 
-impl<T: Copy> Pixels<T> {
-    fn from_fn(f: impl Fn(usize, usize) -> T, length: usize, width: usize) -> Self {
-        let mut values_by_row = Vec::new();
+impl WSet {
+    fn insert(&mut self, w: u8) {
+        self.words[w as usize / 64] |= 1 << (w % 64);
+    }
 
-        for w in 0..width {
-            for l in 0..length {
-                values_by_row.push(f(l, w));
-            }
+    fn contains(&self, w: u8) -> bool {
+        self.words[w as usize / 64] & (1 << (w % 64)) != 0
+    }
+
+    fn contains_range(&self, min_w: u8, max_w: u8) -> bool {
+        (0..self.words.len()).all(|index| {
+            let mask = WSet::mask(index, min_w, max_w);
+            self.words[index] & mask == mask
+        })
+    }
+
+    fn remove_range(&mut self, min_w: u8, max_w: u8) {
+        for index in 0..self.words.len() {
+            self.words[index] &= !WSet::mask(index, min_w, max_w);
         }
+    }
 
-        Pixels { values_by_row, length }
+    fn first(&self) -> Option<u8> {
+        self.words.iter().enumerate()
+            .find(|(_, &word)| word != 0)
+            .map(|(index, word)| (index * 64 + word.trailing_zeros() as usize) as u8)
     }
 
-    fn value(&self, l: usize, w: usize) -> T {
-        self.values_by_row[w * self.length + l]
+    fn is_empty(&self) -> bool {
+        self.words.iter().all(|&word| word == 0)
     }
-}
 
-impl<T: Ord> Pixels<T> {
-    fn max(&self) -> Option<&T> {
-        self.values_by_row.iter().max()
+    fn mask(index: usize, min_w: u8, max_w: u8) -> u64 {
+
+        // Clamp the range [min_w, max_w) to the 64 points covered by this word
+        let word_min = index * 64;
+        let start = (min_w as usize).clamp(word_min, word_min + 64) - word_min;
+        let end = (max_w as usize).clamp(word_min, word_min + 64) - word_min;
+
+        match end - start {
+            0 => 0,
+            64 => u64::MAX,
+            len => ((1u64 << len) - 1) << start
+        }
     }
 }
 
-impl Pixels<RawColor> {
-    fn with_palette<C: Color>(self, palette: &impl Palette<C>) -> Pixels<C> {
-        let new_colors = self.values_by_row.into_iter()
-            .map(|color| palette.nearest(color).unwrap_or_default())
+/* The chunks of a mosaic grouped by the cells of a coarse grid over l and w. Each chunk is listed
+   in every cell it overlaps, so a query only needs to look at the chunks in the cells under it
+   rather than at every chunk in the mosaic. */
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+struct ChunkIndex {
+    chunks_by_cell: BTreeMap<(u32, u32), Vec<(usize, usize)>>
+}
+
+impl ChunkIndex {
+    const CELL_SIZE: u32 = 16;
+
+    fn new<U, B, C>(sections: &[Section<U, B, C>]) -> Self {
+        let mut chunks_by_cell: BTreeMap<(u32, u32), Vec<(usize, usize)>> = BTreeMap::new();
+
+        for (section_index, (section_l, section_w, _, chunks)) in sections.iter().enumerate() {
+            for (chunk_index, chunk) in chunks.iter().enumerate() {
+                let min_l = section_l + chunk.l as u32;
+                let min_w = section_w + chunk.w as u32;
+                let max_l = min_l + chunk.length as u32 - 1;
+                let max_w = min_w + chunk.width as u32 - 1;
+
+                for cell_l in (min_l / ChunkIndex::CELL_SIZE)..=(max_l / ChunkIndex::CELL_SIZE) {
+                    for cell_w in (min_w / ChunkIndex::CELL_SIZE)..=(max_w / ChunkIndex::CELL_SIZE) {
+                        chunks_by_cell.entry((cell_l, cell_w)).or_default().push((section_index, chunk_index));
+                    }
+                }
+            }
+        }
+
+        ChunkIndex { chunks_by_cell }
+    }
+
+    /* Chunks that may overlap the ranges are returned once each, in the order the mosaic stores
+       them. An empty range still matches whatever spans its start, like the overlap check in
+       chunks_in_box, so the cells on both sides of its start are visited. */
+    fn chunks_in(&self, l: &Range<u32>, w: &Range<u32>) -> Vec<(usize, usize)> {
+        let cells = |range: &Range<u32>| {
+            let last = range.end.saturating_sub(1);
+            (range.start.min(last) / ChunkIndex::CELL_SIZE, range.start.max(last) / ChunkIndex::CELL_SIZE)
+        };
+        let (min_cell_l, max_cell_l) = cells(l);
+        let (min_cell_w, max_cell_w) = cells(w);
+
+        let mut chunks: Vec<(usize, usize)> = self.chunks_by_cell.range((min_cell_l, min_cell_w)..=(max_cell_l, max_cell_w))
+            .filter(|&(&(_, cell_w), _)| cell_w >= min_cell_w && cell_w <= max_cell_w)
+            .flat_map(|(_, chunks)| chunks.iter().copied())
             .collect();
-        Pixels { values_by_row: new_colors, length: self.length }
+        chunks.sort_unstable();
+        chunks.dedup();
+
+        chunks
     }
 }
 
-//noinspection DuplicatedCode
-#[cfg(all(test, feature = "default"))]
-mod tests {
-    use rand::prelude::*;
-    use rand_chacha::ChaCha8Rng;
-    use crate::palette::EuclideanDistancePalette;
-    use super::*;
+// Disjoint sets of ids, such as the bricks in a layer that are already joined by the layer below
+#[derive(Clone)]
+struct Groups {
+    parents: Vec<usize>
+}
 
-    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
-    pub(crate) struct TestBrick<'a> {
-        pub(crate) id: &'a str,
-        pub(crate) rotation_count: u8,
-        pub(crate) length: u8,
-        pub(crate) width: u8,
-        pub(crate) height: u8,
-        pub(crate) unit_brick: u8
+impl Groups {
+    fn new(count: usize) -> Self {
+        Groups { parents: (0..count).collect() }
     }
 
-    impl NonUnitBrick<u8> for TestBrick<'_> {
-        fn length(&self) -> u8 {
-            self.length
-        }
+    fn add(&mut self) -> usize {
+        let id = self.parents.len();
+        self.parents.push(id);
+        id
+    }
 
-        fn width(&self) -> u8 {
-            self.width
+    fn root(&mut self, id: usize) -> usize {
+        let mut root = id;
+        while self.parents[root] != root {
+            root = self.parents[root];
         }
 
-        fn height(&self) -> u8 {
-            self.height
+        // Point every id on the path directly at the root so later lookups are faster
+        let mut current = id;
+        while self.parents[current] != root {
+            let next = self.parents[current];
+            self.parents[current] = root;
+            current = next;
         }
 
-        fn unit_brick(&self) -> u8 {
-            self.unit_brick
-        }
+        root
+    }
+
+    fn is_joined(&mut self, id1: usize, id2: usize) -> bool {
+        self.root(id1) == self.root(id2)
+    }
+
+    // The root of id1 stays the root of the joined set
+    fn join(&mut self, id1: usize, id2: usize) {
+        let root1 = self.root(id1);
+        let root2 = self.root(id2);
+        self.parents[root2] = root1;
+    }
+
+    fn count(&mut self) -> usize {
+        (0..self.parents.len()).filter(|&id| self.root(id) == id).count()
+    }
+
+    fn join_all(&mut self, ids: impl Iterator<Item=usize>) {
+        let mut first = None;
+        for id in ids {
+            match first {
+                Some(first) => self.join(first, id),
+                None => first = Some(id)
+            }
+        }
+    }
+}
+
+struct Tile {
+    l: u32,
+    w: u32,
+    tile_length: u32,
+    image_length: u32,
+    image_width: u32,
+    pixels: Vec<RawColor>
+}
+
+impl Image for Tile {
+    fn pixel(&self, l: u32, w: u32) -> RawColor {
+        self.pixels[((w - self.w) * self.tile_length + l - self.l) as usize]
+    }
+
+    fn length(&self) -> u32 {
+        self.image_length
+    }
+
+    fn width(&self) -> u32 {
+        self.image_width
+    }
+}
+
+struct Relief {
+    l: u32,
+    w: u32,
+    padding: u32,
+    heights: HeightMap,
+    near_heights: HeightMap
+}
+
+impl Relief {
+    fn image_pos(&self, l: usize, w: usize) -> (u32, u32) {
+        (l as u32 + self.l - self.padding, w as u32 + self.w - self.padding)
+    }
+
+    fn height(&self, l: usize, w: usize) -> u32 {
+        self.heights.value(l, w)
+    }
+
+    fn is_exposed(&self, l: usize, w: usize, h: u32) -> bool {
+
+        // Sections are padded, so every point inside one has neighbors on all four sides
+        h + 1 >= self.height(l, w)
+            || self.height(l - 1, w) <= h
+            || self.height(l + 1, w) <= h
+            || self.height(l, w - 1) <= h
+            || self.height(l, w + 1) <= h
+    }
+
+    // Whether any column within the padding of the point, including diagonally, ends at or below it
+    fn is_near_side(&self, l: usize, w: usize, h: u32) -> bool {
+        self.near_heights.value(l, w) <= h
+    }
+}
+
+struct Pixels<T> {
+    values_by_row: Vec<T>,
+    length: usize
+}
+
+impl<T: Copy> Pixels<T> {
+    fn from_fn(f: impl Fn(usize, usize) -> T, length: usize, width: usize) -> Self {
+        let mut values_by_row = Vec::new();
+
+        for w in 0..width {
+            for l in 0..length {
+                values_by_row.push(f(l, w));
+            }
+        }
+
+        Pixels { values_by_row, length }
+    }
+
+    fn value(&self, l: usize, w: usize) -> T {
+        self.values_by_row[w * self.length + l]
+    }
+}
+
+impl<T: Copy + Ord> Pixels<T> {
+
+    /* The minimum over a square is the minimum over each of its rows, so take the minimum along
+       each row first and then along each column of those minimums. Each pass is linear in the
+       number of pixels regardless of the radius. */
+    fn window_min(&self, radius: usize, width: usize) -> Self {
+        let row_mins = Pixels {
+            values_by_row: (0..width).flat_map(|w| window_min(self.length, radius, |l| self.value(l, w))).collect(),
+            length: self.length
+        };
+        let column_mins: Vec<Vec<T>> = (0..self.length)
+            .map(|l| window_min(width, radius, |w| row_mins.value(l, w)))
+            .collect();
+
+        Pixels::from_fn(|l, w| column_mins[l][w], self.length, width)
+    }
+}
+
+impl Pixels<RawColor> {
+
+    // Pixels without a nearest color take the default color, and their positions are returned alongside
+    fn with_palette<C: Color>(self, palette: &impl Palette<C>) -> (Pixels<C>, Vec<(usize, usize)>) {
+        let mut defaulted = Vec::new();
+        let mut new_colors = Vec::with_capacity(self.values_by_row.len());
+
+        for (index, color) in self.values_by_row.into_iter().enumerate() {
+            new_colors.push(palette.nearest(color).unwrap_or_else(|| {
+                defaulted.push((index % self.length, index / self.length));
+                C::default()
+            }));
+        }
+
+        (Pixels { values_by_row: new_colors, length: self.length }, defaulted)
+    }
+}
+
+//noinspection DuplicatedCode
+#[cfg(all(test, feature = "default"))]
+mod tests {
+    use rand::prelude::*;
+    use rand_chacha::ChaCha8Rng;
+    use crate::palette::EuclideanDistancePalette;
+    use super::*;
+
+    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+    pub(crate) struct TestBrick<'a> {
+        pub(crate) id: &'a str,
+        pub(crate) rotation_count: u8,
+        pub(crate) length: u8,
+        pub(crate) width: u8,
+        pub(crate) height: u8,
+        pub(crate) unit_brick: u8
+    }
+
+    impl NonUnitBrick<u8> for TestBrick<'_> {
+        fn length(&self) -> u8 {
+            self.length
+        }
+
+        fn width(&self) -> u8 {
+            self.width
+        }
+
+        fn height(&self) -> u8 {
+            self.height
+        }
+
+        fn unit_brick(&self) -> u8 {
+            self.unit_brick
+        }
 
         fn rotate_90(&self) -> Self {
             TestBrick {
@@ -1049,7 +2621,7 @@ mod tests {
     pub(crate) const COLOR_3: TestColor = TestColor::new(52, 235, 55, 255);
     pub(crate) const COLOR_4: TestColor = TestColor::new(52, 147, 235, 255);
 
-    struct TestImage {
+    pub(crate) struct TestImage {
         colors: Pixels<RawColor>,
         length: u32,
         width: u32
@@ -1062,7 +2634,7 @@ mod tests {
     }
 
     impl TestImage {
-        fn new(length: u32, width: u32) -> Self {
+        pub(crate) fn new(length: u32, width: u32) -> Self {
             TestImage {
                 colors: Pixels {
                     values_by_row: vec![RawColor { red: 0, green: 0, blue: 0, alpha: 0 }; length as usize * width as usize],
@@ -1073,7 +2645,7 @@ mod tests {
             }
         }
 
-        fn put_pixel(&mut self, l: u32, w: u32, new_pixel: RawColor) {
+        pub(crate) fn put_pixel(&mut self, l: u32, w: u32, new_pixel: RawColor) {
             *self.colors.value_mut(l as usize, w as usize) = new_pixel
         }
     }
@@ -1098,13 +2670,13 @@ mod tests {
 
     fn assert_colors_match_img(img: &TestImage, section_l: u32, section_w: u32, chunk: &Chunk<u8, TestBrick, TestColor>) {
         for l in 0..chunk.length {
-            for &w in &chunk.ws_included[l as usize] {
-                assert_eq!(img.pixel((l + chunk.l) as u32 + section_l, (w + chunk.w) as u32 + section_w), chunk.color.value);
+            for w in (0..chunk.width).filter(|&w| chunk.ws_included[l as usize].contains(w)) {
+                assert_eq!(img.pixel(l as u32 + chunk.l as u32 + section_l, w as u32 + chunk.w as u32 + section_w), chunk.color.value);
             }
         }
     }
 
-    fn make_test_img() -> (TestImage, impl Palette<TestColor>) {
+    pub(crate) fn make_test_img() -> (TestImage, impl Palette<TestColor>) {
         let color1 = COLOR_1.value;
         let color2 = COLOR_2.value;
         let color3 = COLOR_3.value;
@@ -1199,7 +2771,7 @@ mod tests {
             assert_eq!(5, chunks.len());
             for chunk in chunks {
                 assert_eq!(1, chunk.height);
-                assert_colors_match_img(&img, *l, *w, &chunk);
+                assert_colors_match_img(&img, *l, *w, chunk);
                 total_bricks += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
 
                 chunk.bricks.iter().for_each(|brick| {
@@ -1231,7 +2803,7 @@ mod tests {
             assert_eq!(5, chunks.len());
             for chunk in chunks {
                 assert_eq!(2, chunk.height);
-                assert_colors_match_img(&img, *l, *w, &chunk);
+                assert_colors_match_img(&img, *l, *w, chunk);
                 total_bricks += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
 
                 chunk.bricks.iter().for_each(|brick| {
@@ -1271,7 +2843,7 @@ mod tests {
             assert_eq!(0, *w);
             assert_eq!(0, *h);
             for chunk in chunks {
-                assert_colors_match_img(&img, *l, *w, &chunk);
+                assert_colors_match_img(&img, *l, *w, chunk);
                 total_bricks += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
             }
         }
@@ -1318,9 +2890,9 @@ mod tests {
             assert_eq!(0, *w);
             assert_eq!(0, *h);
             for chunk in chunks {
-                assert_colors_match_img(&img, *l, *w, &chunk);
+                assert_colors_match_img(&img, *l, *w, chunk);
 
-                if (*w + chunk.w as u32) % 2 == 0 {
+                if (*w + chunk.w as u32).is_multiple_of(2) {
                     total_bricks_even += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
                 } else {
                     total_bricks_odd += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
@@ -1351,8 +2923,8 @@ mod tests {
             }
         }
 
-        let heights: Vec<Vec<u32>> = (0..size).into_iter().map(
-            |_| (0..size).into_iter().map(|_| rng.gen_range(0..6)).collect()
+        let heights: Vec<Vec<u32>> = (0..size).map(
+            |_| (0..size).map(|_| rng.gen_range(0..6)).collect()
         ).collect();
         let expected_total_bricks_even: u32 = heights.iter().enumerate()
             .filter(|(index, _)| index % 2 == 0)
@@ -1382,9 +2954,9 @@ mod tests {
             assert_eq!(0, *w);
             assert_eq!(0, *h);
             for chunk in chunks {
-                assert_colors_match_img(&img, *l, *w, &chunk);
+                assert_colors_match_img(&img, *l, *w, chunk);
 
-                if (*w + chunk.w as u32) % 2 == 0 {
+                if (*w + chunk.w as u32).is_multiple_of(2) {
                     total_bricks_even += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
                 } else {
                     total_bricks_odd += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
@@ -1415,8 +2987,8 @@ mod tests {
             }
         }
 
-        let heights: Vec<Vec<u32>> = (0..size).into_iter().map(
-            |_| (0..size).into_iter().map(|_| rng.gen_range(0..6)).collect()
+        let heights: Vec<Vec<u32>> = (0..size).map(
+            |_| (0..size).map(|_| rng.gen_range(0..6)).collect()
         ).collect();
         let expected_total_bricks_even: u32 = heights.iter().enumerate()
             .filter(|(index, _)| index % 2 == 0)
@@ -1446,9 +3018,9 @@ mod tests {
             assert!(*w == 0 || *w == 255);
             assert_eq!(*h, 0);
             for chunk in chunks {
-                assert_colors_match_img(&img, *l, *w, &chunk);
+                assert_colors_match_img(&img, *l, *w, chunk);
 
-                if (*w + chunk.w as u32) % 2 == 0 {
+                if (*w + chunk.w as u32).is_multiple_of(2) {
                     total_bricks_even += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
                 } else {
                     total_bricks_odd += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
@@ -1466,8 +3038,8 @@ mod tests {
         let size = u8::MAX as u32 - 1;
         let mut rng = ChaCha8Rng::seed_from_u64(1705276380);
 
-        let heights: Vec<Vec<u32>> = (0..img.width).into_iter().map(
-            |_| (0..img.length).into_iter().map(|_| rng.gen_range((size - 6)..size)).collect()
+        let heights: Vec<Vec<u32>> = (0..img.width).map(
+            |_| (0..img.length).map(|_| rng.gen_range((size - 6)..size)).collect()
         ).collect();
         let expected_total_bricks_even: u32 = heights.iter().enumerate()
             .filter(|(index, _)| index % 2 == 0)
@@ -1497,9 +3069,9 @@ mod tests {
             assert_eq!(0, *w);
             assert_eq!(0, *h);
             for chunk in chunks {
-                assert_colors_match_img(&img, *l, *w, &chunk);
+                assert_colors_match_img(&img, *l, *w, chunk);
 
-                if (*w + chunk.w as u32) % 2 == 0 {
+                if (*w + chunk.w as u32).is_multiple_of(2) {
                     total_bricks_even += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
                 } else {
                     total_bricks_odd += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
@@ -1517,8 +3089,8 @@ mod tests {
         let size = 300;
         let mut rng = ChaCha8Rng::seed_from_u64(1705276380);
 
-        let heights: Vec<Vec<u32>> = (0..img.width).into_iter().map(
-            |_| (0..img.length).into_iter().map(|_| rng.gen_range((size - 6)..size)).collect()
+        let heights: Vec<Vec<u32>> = (0..img.width).map(
+            |_| (0..img.length).map(|_| rng.gen_range((size - 6)..size)).collect()
         ).collect();
         let expected_total_bricks_even: u32 = heights.iter().enumerate()
             .filter(|(index, _)| index % 2 == 0)
@@ -1548,9 +3120,9 @@ mod tests {
             assert_eq!(*w, 0);
             assert!(*h == 0 || *h == 255);
             for chunk in chunks {
-                assert_colors_match_img(&img, *l, *w, &chunk);
+                assert_colors_match_img(&img, *l, *w, chunk);
 
-                if (*w + chunk.w as u32) % 2 == 0 {
+                if (*w + chunk.w as u32).is_multiple_of(2) {
                     total_bricks_even += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
                 } else {
                     total_bricks_odd += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
@@ -1649,9 +3221,9 @@ mod tests {
             assert_eq!(0, *w);
             assert_eq!(0, *h);
             for chunk in chunks {
-                assert_colors_match_img(&img, *l, *w, &chunk);
+                assert_colors_match_img(&img, *l, *w, chunk);
 
-                if (*w + chunk.w as u32) % 2 == 0 {
+                if (*w + chunk.w as u32).is_multiple_of(2) {
                     total_bricks_even += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
                 } else {
                     total_bricks_odd += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
@@ -1710,9 +3282,9 @@ mod tests {
             assert_eq!(0, *w);
             assert_eq!(0, *h);
             for chunk in chunks {
-                assert_colors_match_img(&img, *l, *w, &chunk);
+                assert_colors_match_img(&img, *l, *w, chunk);
 
-                if (*w + chunk.w as u32) % 2 == 0 {
+                if (*w + chunk.w as u32).is_multiple_of(2) {
                     total_bricks_even += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
                 } else {
                     total_bricks_odd += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
@@ -1764,9 +3336,9 @@ mod tests {
             assert_eq!(0, *w);
             assert_eq!(0, *h);
             for chunk in chunks {
-                assert_colors_match_img(&img, *l, *w, &chunk);
+                assert_colors_match_img(&img, *l, *w, chunk);
 
-                if (*w + chunk.w as u32) % 2 == 0 {
+                if (*w + chunk.w as u32).is_multiple_of(2) {
                     total_bricks_even += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
                 } else {
                     total_bricks_odd += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
@@ -1817,9 +3389,9 @@ mod tests {
             assert_eq!(0, *w);
             assert_eq!(0, *h);
             for chunk in chunks {
-                assert_colors_match_img(&img, *l, *w, &chunk);
+                assert_colors_match_img(&img, *l, *w, chunk);
 
-                if (*w + chunk.w as u32) % 2 == 0 {
+                if (*w + chunk.w as u32).is_multiple_of(2) {
                     total_bricks_even += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
                 } else {
                     total_bricks_odd += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
@@ -1876,9 +3448,9 @@ mod tests {
             assert_eq!(0, *w);
             assert_eq!(0, *h);
             for chunk in chunks {
-                assert_colors_match_img(&img, *l, *w, &chunk);
+                assert_colors_match_img(&img, *l, *w, chunk);
 
-                if (*w + chunk.w as u32) % 2 == 0 {
+                if (*w + chunk.w as u32).is_multiple_of(2) {
                     total_bricks_even += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
                 } else {
                     total_bricks_odd += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
@@ -1932,7 +3504,7 @@ mod tests {
             assert_eq!(0, *w);
             assert_eq!(0, *h);
             for chunk in chunks {
-                assert_colors_match_img(&img, *l, *w, &chunk);
+                assert_colors_match_img(&img, *l, *w, chunk);
 
                 if chunk.l % 2 == 0 {
                     total_bricks_even += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
@@ -1993,9 +3565,9 @@ mod tests {
             assert_eq!(0, *w);
             assert_eq!(0, *h);
             for chunk in chunks {
-                assert_colors_match_img(&img, *l, *w, &chunk);
+                assert_colors_match_img(&img, *l, *w, chunk);
 
-                if (*w + chunk.w as u32) % 2 == 0 {
+                if (*w + chunk.w as u32).is_multiple_of(2) {
                     total_bricks_even += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
                 } else {
                     total_bricks_odd += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
@@ -2054,9 +3626,9 @@ mod tests {
             assert_eq!(0, *w);
             assert_eq!(0, *h);
             for chunk in chunks {
-                assert_colors_match_img(&img, *l, *w, &chunk);
+                assert_colors_match_img(&img, *l, *w, chunk);
 
-                if (*w + chunk.w as u32) % 2 == 0 {
+                if (*w + chunk.w as u32).is_multiple_of(2) {
                     total_bricks_even += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
                 } else {
                     total_bricks_odd += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
@@ -2114,9 +3686,9 @@ mod tests {
             assert_eq!(0, *w);
             assert_eq!(0, *h);
             for chunk in chunks {
-                assert_colors_match_img(&img, *l, *w, &chunk);
+                assert_colors_match_img(&img, *l, *w, chunk);
 
-                if (*w + chunk.w as u32) % 2 == 0 {
+                if (*w + chunk.w as u32).is_multiple_of(2) {
                     total_bricks_even += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
                 } else {
                     total_bricks_odd += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
@@ -2174,9 +3746,9 @@ mod tests {
             assert_eq!(0, *w);
             assert_eq!(0, *h);
             for chunk in chunks {
-                assert_colors_match_img(&img, *l, *w, &chunk);
+                assert_colors_match_img(&img, *l, *w, chunk);
 
-                if (*w + chunk.w as u32) % 2 == 0 {
+                if (*w + chunk.w as u32).is_multiple_of(2) {
                     total_bricks_even += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
                 } else {
                     total_bricks_odd += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
@@ -2234,9 +3806,9 @@ mod tests {
             assert_eq!(0, *w);
             assert_eq!(0, *h);
             for chunk in chunks {
-                assert_colors_match_img(&img, *l, *w, &chunk);
+                assert_colors_match_img(&img, *l, *w, chunk);
 
-                if (*w + chunk.w as u32) % 2 == 0 {
+                if (*w + chunk.w as u32).is_multiple_of(2) {
                     total_bricks_even += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
                 } else {
                     total_bricks_odd += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
@@ -2289,9 +3861,9 @@ mod tests {
             assert_eq!(0, *w);
             assert_eq!(0, *h);
             for chunk in chunks {
-                assert_colors_match_img(&img, *l, *w, &chunk);
+                assert_colors_match_img(&img, *l, *w, chunk);
 
-                if (*w + chunk.w as u32) % 2 == 0 {
+                if (*w + chunk.w as u32).is_multiple_of(2) {
                     total_bricks_even += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
                 } else {
                     total_bricks_odd += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
@@ -2343,9 +3915,9 @@ mod tests {
             assert_eq!(0, *w);
             assert_eq!(0, *h);
             for chunk in chunks {
-                assert_colors_match_img(&img, *l, *w, &chunk);
+                assert_colors_match_img(&img, *l, *w, chunk);
 
-                if (*w + chunk.w as u32) % 2 == 0 {
+                if (*w + chunk.w as u32).is_multiple_of(2) {
                     total_bricks_even += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
                 } else {
                     total_bricks_odd += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
@@ -2397,9 +3969,9 @@ mod tests {
             assert_eq!(0, *w);
             assert_eq!(0, *h);
             for chunk in chunks {
-                assert_colors_match_img(&img, *l, *w, &chunk);
+                assert_colors_match_img(&img, *l, *w, chunk);
 
-                if (*w + chunk.w as u32) % 2 == 0 {
+                if (*w + chunk.w as u32).is_multiple_of(2) {
                     total_bricks_even += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
                 } else {
                     total_bricks_odd += chunk.bricks.iter().map(|brick| volume(brick.brick)).sum::<u32>();
@@ -2411,4 +3983,1457 @@ mod tests {
         assert_eq!(total_bricks_even + total_bricks_odd, mosaic.iter().fold(0, |total, brick| total + volume(brick.brick)));
         assert!(mosaic.iter().all(|brick| brick.brick.length() == 1 && brick.brick.width() == 1));
     }
+
+    pub(crate) fn make_single_color_img(length: u32, width: u32) -> TestImage {
+        let mut img = TestImage::new(length, width);
+        for l in 0..length {
+            for w in 0..width {
+                img.put_pixel(l, w, COLOR_1.value);
+            }
+        }
+
+        img
+    }
+
+    struct PartialPalette;
+
+    impl Palette<TestColor> for PartialPalette {
+        fn nearest(&self, color: RawColor) -> Option<TestColor> {
+            match color == COLOR_4.value {
+                true => None,
+                false => Some(COLOR_1)
+            }
+        }
+    }
+
+    #[test]
+    fn test_warnings_for_empty_image() {
+        let (_, palette) = make_test_img();
+
+        let mosaic = Mosaic::<u8, TestBrick, TestColor>::from_image(
+            &TestImage::new(0, 3),
+            &palette,
+            |_, _, _| 1,
+            |_, _, _, _| UNIT_BRICK
+        ).unwrap();
+
+        assert_eq!(0, mosaic.iter().count());
+        assert_eq!(&[MosaicWarning::EmptyImage], mosaic.warnings());
+    }
+
+    #[test]
+    fn test_warnings_for_default_colors() {
+        let (img, palette) = make_test_img();
+
+        let mosaic = Mosaic::<u8, TestBrick, TestColor>::from_image(
+            &img,
+            &EuclideanDistancePalette::new(&[]),
+            |_, _, _| 1,
+            |_, _, _, _| UNIT_BRICK
+        ).unwrap();
+        assert_eq!(&[MosaicWarning::DefaultColor(20, 0, 0)], mosaic.warnings());
+
+        let mosaic = Mosaic::<u8, TestBrick, TestColor>::from_image(&img, &PartialPalette, |_, _, _| 1, |_, _, _, _| UNIT_BRICK).unwrap();
+        let defaulted: Vec<_> = (0..img.width())
+            .flat_map(|w| (0..img.length()).map(move |l| (l, w)))
+            .filter(|&(l, w)| img.pixel(l, w) == COLOR_4.value)
+            .collect();
+        assert!(!defaulted.is_empty());
+        assert_eq!(&[MosaicWarning::DefaultColor(defaulted.len() as u64, defaulted[0].0, defaulted[0].1)], mosaic.warnings());
+
+        let mosaic = Mosaic::<u8, TestBrick, TestColor>::from_image(&img, &palette, |_, _, _| 1, |_, _, _, _| UNIT_BRICK).unwrap();
+        assert!(mosaic.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_default_color_warnings_across_sections() {
+        let mut img = TestImage::new(300, 2);
+        for l in 0..300 {
+            for w in 0..2 {
+                img.put_pixel(l, w, COLOR_1.value);
+            }
+        }
+        img.put_pixel(280, 0, COLOR_4.value);
+        img.put_pixel(10, 1, COLOR_4.value);
+        img.put_pixel(254, 1, COLOR_4.value);
+        img.put_pixel(255, 1, COLOR_4.value);
+
+        // Pixels in the padding around each section are only counted by the section they belong to
+        let mosaic = Mosaic::<u8, TestBrick, TestColor>::from_image(&img, &PartialPalette, |_, _, _| 1, |_, _, _, _| UNIT_BRICK).unwrap();
+        assert_eq!(&[MosaicWarning::DefaultColor(4, 280, 0)], mosaic.warnings());
+    }
+
+    #[test]
+    fn test_default_color_warnings_on_large_image() {
+        let img = TestImage::new(600, 600);
+
+        let mosaic = Mosaic::<u8, TestBrick, TestColor>::from_image(
+            &img,
+            &EuclideanDistancePalette::new(&[]),
+            |_, _, _| 1,
+            |_, _, _, _| UNIT_BRICK
+        ).unwrap();
+        assert_eq!(&[MosaicWarning::DefaultColor(360000, 0, 0)], mosaic.warnings());
+
+        let reduced = mosaic.reduce_bricks(&[TWO_BY_ONE_PLATE, ZERO_BY_TWO_PLATE], &[]).unwrap()
+            .reduce_bricks(&[ZERO_BY_TWO_PLATE], &[]).unwrap();
+        assert_eq!(&[MosaicWarning::DefaultColor(360000, 0, 0), MosaicWarning::ZeroSizedBrick(ZERO_BY_TWO_PLATE)], reduced.warnings());
+    }
+
+    #[test]
+    fn test_warnings_for_unusable_bricks() {
+        let (img, palette) = make_test_img();
+
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image(&img, &palette, |_, _, _| 2, |_, _, _, _| UNIT_BRICK).unwrap();
+        let reduced = mosaic.clone().reduce_bricks(
+            &[TWO_BY_TWO_PLATE, ZERO_BY_TWO_PLATE, ONE_BY_ONE_BY_SIX_BRICK, ZERO_HEIGHT_BRICK, TWO_BY_ONE_PLATE_2],
+            &[]
+        ).unwrap();
+
+        let expected = vec![
+            MosaicWarning::ZeroSizedBrick(ZERO_BY_TWO_PLATE),
+            MosaicWarning::BrickTooTall(ONE_BY_ONE_BY_SIX_BRICK),
+            MosaicWarning::ZeroSizedBrick(ZERO_HEIGHT_BRICK)
+        ];
+        assert_eq!(expected, reduced.warnings());
+
+        let expected_bricks: Vec<_> = mosaic.clone().reduce_bricks(&[TWO_BY_TWO_PLATE, TWO_BY_ONE_PLATE_2], &[]).unwrap().iter().collect();
+        assert_eq!(expected_bricks, reduced.iter().collect::<Vec<_>>());
+
+        // Warnings carry through later steps
+        let transformed = reduced.rotate_90();
+        assert_eq!(expected, transformed.warnings());
+    }
+
+    #[test]
+    fn test_validate_generated_mosaics() {
+        let (img, palette) = make_test_img();
+        let heights = [
+            [5, 2, 1, 1],
+            [5, 5, 2, 2],
+            [1, 0, 3, 2],
+            [4, 3, 1, 2],
+            [3, 1, 1, 4]
+        ];
+
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image(
+            &img,
+            &palette,
+            |l, w, _| heights[w as usize][l as usize],
+            |_, _, _, _| UNIT_BRICK
+        ).unwrap();
+        assert!(mosaic.validate().is_empty());
+
+        let reduced = mosaic.reduce_bricks(&[TWO_BY_TWO_PLATE, TWO_BY_ONE_PLATE, TWO_BY_TWO_BY_TWO_BRICK], &[]).unwrap();
+        assert!(reduced.validate().is_empty());
+        assert!(reduced.clone().rotate_90().mirror_w().translate(2, 1).validate().is_empty());
+        assert!(reduced.clone().crop(1..3, 0..4).validate().is_empty());
+        assert!(reduced.clone().paste(&reduced.clone().crop(0..2, 0..2), 3, 4).validate().is_empty());
+    }
+
+    #[test]
+    fn test_validate_finds_broken_bricks() {
+        let (_, palette) = make_test_img();
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image(&make_single_color_img(2, 2), &palette, |_, _, _| 1, |_, _, _, _| UNIT_BRICK)
+            .unwrap()
+            .reduce_bricks(&[TWO_BY_TWO_PLATE], &[])
+            .unwrap();
+        let broken = |change: &dyn Fn(&mut Mosaic<u8, TestBrick, TestColor>)| {
+            let mut broken = mosaic.clone();
+            change(&mut broken);
+            broken.validate()
+        };
+
+        assert_eq!(
+            vec![
+                ValidationIssue::UncoveredPoint(0, 0, 0),
+                ValidationIssue::UncoveredPoint(0, 1, 0),
+                ValidationIssue::UncoveredPoint(1, 0, 0),
+                ValidationIssue::UncoveredPoint(1, 1, 0)
+            ],
+            broken(&|mosaic| mosaic.sections[0].3[0].bricks.clear())
+        );
+        assert_eq!(
+            vec![
+                ValidationIssue::OverlappingBricks(0, 0, 0),
+                ValidationIssue::OverlappingBricks(0, 1, 0),
+                ValidationIssue::OverlappingBricks(1, 0, 0),
+                ValidationIssue::OverlappingBricks(1, 1, 0)
+            ],
+            broken(&|mosaic| {
+                let brick = mosaic.sections[0].3[0].bricks[0].clone();
+                mosaic.sections[0].3[0].bricks.push(brick);
+            })
+        );
+        assert_eq!(
+            vec![
+                ValidationIssue::UncoveredPoint(0, 1, 0),
+                ValidationIssue::UncoveredPoint(1, 1, 0),
+                ValidationIssue::WrongUnitBrick(0, 0, 0)
+            ],
+            broken(&|mosaic| mosaic.sections[0].3[0].bricks[0].brick = Brick::NonUnit(TWO_BY_ONE_PLATE_2))
+        );
+        assert_eq!(
+            vec![
+                ValidationIssue::UncoveredPoint(0, 0, 0),
+                ValidationIssue::UncoveredPoint(0, 1, 0),
+                ValidationIssue::BrickOutsideChunk(1, 0, 0),
+                ValidationIssue::OutOfBounds(1, 0, 0)
+            ],
+            broken(&|mosaic| mosaic.sections[0].3[0].bricks[0].l = 1)
+        );
+        assert_eq!(vec![ValidationIssue::OutOfBounds(0, 0, 0)], broken(&|mosaic| mosaic.length = 1));
+    }
+
+    #[test]
+    fn test_reduce_staggered_zero_penalty_matches_reduce() {
+        let (img, palette) = make_test_img();
+
+        let heights = [
+            [5, 2, 1, 1],
+            [5, 5, 2, 2],
+            [1, 0, 3, 2],
+            [4, 3, 1, 2],
+            [3, 1, 1, 4]
+        ];
+
+        let mosaic = Mosaic::from_image(
+            &img,
+            &palette,
+            |l, w, _| heights[w as usize][l as usize],
+            |_, _, _, _| UNIT_BRICK
+        ).unwrap();
+
+        let bricks = [TWO_BY_ONE_PLATE, THREE_BY_ONE_PLATE, TWO_BY_TWO_PLATE, TWO_BY_ONE_BY_TWO_BRICK];
+        let reduced = mosaic.clone().reduce_bricks(&bricks, &[]).unwrap();
+        let staggered = mosaic.reduce_bricks_staggered(&bricks, &[], 0).unwrap();
+
+        assert_eq!(reduced, staggered);
+    }
+
+    #[test]
+    fn test_reduce_staggered_avoids_aligned_seams() {
+        let img = make_single_color_img(4, 1);
+        let (_, palette) = make_test_img();
+
+        let mosaic = Mosaic::from_image(
+            &img,
+            &palette,
+            |_, _, _| 2,
+            |_, _, _, _| UNIT_BRICK
+        ).unwrap();
+
+        let greedy = mosaic.clone().reduce_bricks(&[TWO_BY_ONE_PLATE, THREE_BY_ONE_PLATE], &[]).unwrap();
+        let greedy_strength = greedy.strength();
+        assert_eq!(1, greedy_strength.len());
+        assert_eq!(1, greedy_strength[0].seams);
+        assert_eq!(1, greedy_strength[0].aligned_seams);
+        assert_eq!(0f64, greedy_strength[0].score());
+
+        let staggered = mosaic.reduce_bricks_staggered(&[TWO_BY_ONE_PLATE, THREE_BY_ONE_PLATE], &[], 2).unwrap();
+        let staggered_strength = staggered.strength();
+        assert_eq!(1, staggered_strength.len());
+        assert_eq!(1, staggered_strength[0].seams);
+        assert_eq!(0, staggered_strength[0].aligned_seams);
+        assert_eq!(1f64, staggered_strength[0].score());
+        assert_eq!(8, staggered.iter().fold(0, |total, brick| total + volume(brick.brick)));
+        assert_eq!(4, staggered.iter().count());
+    }
+
+    #[test]
+    fn test_strength_single_layer_has_no_seams() {
+        let img = make_single_color_img(4, 4);
+        let (_, palette) = make_test_img();
+
+        let mosaic = Mosaic::from_image(
+            &img,
+            &palette,
+            |_, _, _| 1,
+            |_, _, _, _| UNIT_BRICK
+        ).unwrap().reduce_bricks_staggered(&[TWO_BY_ONE_PLATE], &[], 5).unwrap();
+
+        let strength = mosaic.strength();
+        assert_eq!(1, strength.len());
+        assert_eq!(0, strength[0].seams);
+        assert_eq!(1f64, strength[0].score());
+    }
+
+    #[test]
+    fn test_filler_colors_hidden_points() {
+        let (img, palette) = make_test_img();
+
+        let mosaic = Mosaic::from_image_with_filler(
+            &img,
+            &palette,
+            |_, _, _| 3,
+            |_, _, _, _| UNIT_BRICK,
+            COLOR_2
+        ).unwrap();
+
+        assert_eq!(4 * 5 * 3, mosaic.iter().fold(0, |total, brick| total + volume(brick.brick)));
+        for brick in mosaic.iter() {
+            let is_hidden = brick.l > 0 && brick.l < 3 && brick.w > 0 && brick.w < 4 && brick.h < 2;
+            match is_hidden {
+                true => assert_eq!(COLOR_2, brick.color),
+                false => assert_eq!(img.pixel(brick.l, brick.w), brick.color.value)
+            }
+        }
+    }
+
+    #[test]
+    fn test_filler_exposes_sides_of_taller_neighbors() {
+        let (img, palette) = make_test_img();
+
+        let heights = [
+            [5, 2, 1, 1],
+            [5, 5, 2, 2],
+            [1, 5, 5, 2],
+            [4, 5, 5, 2],
+            [3, 1, 1, 4]
+        ];
+
+        let mosaic = Mosaic::from_image_with_filler(
+            &img,
+            &palette,
+            |l, w, _| heights[w as usize][l as usize],
+            |_, _, _, _| UNIT_BRICK,
+            COLOR_2
+        ).unwrap();
+
+        // (1, 2) is surrounded by columns of heights 5, 1, 5, and 5
+        let column: Vec<PlacedBrick<u8, TestBrick, TestColor>> = mosaic.iter()
+            .filter(|brick| brick.l == 1 && brick.w == 2)
+            .collect();
+        assert_eq!(5, column.len());
+        for brick in column {
+            match brick.h == 0 {
+                true => assert_eq!(COLOR_2, brick.color),
+                false => assert_eq!(img.pixel(1, 2), brick.color.value)
+            }
+        }
+    }
+
+    #[test]
+    fn test_filler_across_sections() {
+        let img = make_single_color_img(300, 3);
+        let (_, palette) = make_test_img();
+
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image_with_filler(
+            &img,
+            &palette,
+            |_, _, _| 2,
+            |_, _, _, _| UNIT_BRICK,
+            COLOR_2
+        ).unwrap();
+
+        for brick in mosaic.iter() {
+            let is_hidden = brick.l > 0 && brick.l < 299 && brick.w == 1 && brick.h == 0;
+            match is_hidden {
+                true => assert_eq!(COLOR_2, brick.color),
+                false => assert_eq!(COLOR_1, brick.color)
+            }
+        }
+    }
+
+    #[test]
+    fn test_filler_reduces_brick_count() {
+        let (_, palette) = make_test_img();
+        let size = 12;
+
+        let mut rng = ChaCha8Rng::seed_from_u64(1705276380);
+        let mut img = TestImage::new(size, size);
+        for l in 0..size {
+            for w in 0..size {
+                let color = match rng.gen_range(0..4) {
+                    0 => COLOR_1,
+                    1 => COLOR_2,
+                    2 => COLOR_3,
+                    _ => COLOR_4
+                };
+                img.put_pixel(l, w, color.value);
+            }
+        }
+
+        let bricks = [TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, FOUR_BY_FOUR_PLATE, FOUR_BY_FOUR_BY_TWO_BRICK];
+        let solid = Mosaic::from_image(
+            &img,
+            &palette,
+            |_, _, _| 6,
+            |_, _, _, _| UNIT_BRICK
+        ).unwrap().reduce_bricks(&bricks, &[]).unwrap();
+        let filled = Mosaic::from_image_with_filler(
+            &img,
+            &palette,
+            |_, _, _| 6,
+            |_, _, _, _| UNIT_BRICK,
+            COLOR_1
+        ).unwrap().reduce_bricks(&bricks, &[]).unwrap();
+
+        assert_eq!(size * size * 6, filled.iter().fold(0, |total, brick| total + volume(brick.brick)));
+        assert!(filled.iter().count() < solid.iter().count());
+    }
+
+    #[test]
+    fn test_hollow_keeps_walls_and_lid() {
+        let img = make_single_color_img(10, 10);
+        let (_, palette) = make_test_img();
+
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image_hollow(
+            &img,
+            &palette,
+            |_, _, _| 6,
+            |_, _, _, _| UNIT_BRICK,
+            1,
+            0
+        ).unwrap();
+
+        // Two full layers on top, then a one-stud ring around the remaining four layers
+        assert_eq!(10 * 10 * 2 + 36 * 4, mosaic.iter().fold(0, |total, brick| total + volume(brick.brick)));
+        assert!(mosaic.iter().all(|brick| brick.h >= 4 || brick.l == 0 || brick.l == 9 || brick.w == 0 || brick.w == 9));
+    }
+
+    #[test]
+    fn test_hollow_adds_supports() {
+        let img = make_single_color_img(10, 10);
+        let (_, palette) = make_test_img();
+
+        let mosaic = Mosaic::from_image_hollow(
+            &img,
+            &palette,
+            |_, _, _| 6,
+            |_, _, _, _| UNIT_BRICK,
+            1,
+            4
+        ).unwrap().reduce_bricks_staggered(&[TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, FOUR_BY_TWO_PLATE], &[], 4).unwrap();
+
+        assert_eq!(10 * 10 * 2 + 36 * 4 + 4 * 4, mosaic.iter().fold(0, |total, brick| total + volume(brick.brick)));
+        let graph = ConnectivityGraph::new(mosaic.iter());
+        for (l, w) in [(4, 4), (4, 8), (8, 4), (8, 8)] {
+            assert!((0..6).all(|h| graph.brick_at(l, w, h).is_some()));
+        }
+        assert_eq!(1, graph.components().len());
+    }
+
+    #[test]
+    fn test_hollow_thick_walls_across_sections() {
+        let img = make_single_color_img(300, 8);
+        let (_, palette) = make_test_img();
+
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image_hollow(
+            &img,
+            &palette,
+            |_, _, _| 5,
+            |_, _, _, _| UNIT_BRICK,
+            2,
+            0
+        ).unwrap();
+
+        // Only the points at least two studs from every side are hollowed out below the lid
+        let hollow_points = (300 - 4) * (8 - 4) * (5 - 2);
+        assert_eq!(300 * 8 * 5 - hollow_points, mosaic.iter().fold(0, |total, brick| total + volume(brick.brick)));
+    }
+
+    #[test]
+    fn test_window_min_matches_every_window() {
+        let length = 23;
+        let width = 17;
+        let heights = HeightMap::from_fn(|l, w| ((l * 7 + w * 13) % 11 + (l * w) % 5) as u32, length, width);
+
+        for radius in [0, 1, 2, 5, 30] {
+            let mins = heights.window_min(radius, width);
+
+            for w in 0..width {
+                for l in 0..length {
+                    let expected = (l.saturating_sub(radius)..(l + radius + 1).min(length))
+                        .flat_map(|other_l| (w.saturating_sub(radius)..(w + radius + 1).min(width)).map(move |other_w| (other_l, other_w)))
+                        .map(|(other_l, other_w)| heights.value(other_l, other_w))
+                        .min()
+                        .unwrap();
+                    assert_eq!(expected, mins.value(l, w));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_hollow_follows_varied_heights() {
+        let (img, palette) = make_test_img();
+
+        let heights = [
+            [5, 2, 1, 1],
+            [5, 5, 2, 2],
+            [1, 0, 3, 2],
+            [4, 3, 1, 2],
+            [3, 1, 1, 4]
+        ];
+
+        let solid: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image(
+            &img,
+            &palette,
+            |l, w, _| heights[w as usize][l as usize],
+            |_, _, _, _| UNIT_BRICK
+        ).unwrap();
+        let hollow: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image_hollow(
+            &img,
+            &palette,
+            |l, w, _| heights[w as usize][l as usize],
+            |_, _, _, _| UNIT_BRICK,
+            1,
+            0
+        ).unwrap();
+
+        // Every point in a 4x5 image is on the edge or next to a shorter column
+        assert_eq!(solid, hollow);
+    }
+
+    #[test]
+    fn test_hollow_huge_wall_is_solid() {
+        let (img, palette) = make_test_img();
+
+        let solid: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image(
+            &img,
+            &palette,
+            |_, _, _| 6,
+            |_, _, _, _| UNIT_BRICK
+        ).unwrap();
+        let hollow: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image_hollow(
+            &img,
+            &palette,
+            |_, _, _| 6,
+            |_, _, _, _| UNIT_BRICK,
+            u32::MAX,
+            0
+        ).unwrap();
+
+        assert_eq!(solid, hollow);
+    }
+
+    fn make_block_img(length: u32, width: u32, block_l: u32, block_w: u32, block_length: u32, block_width: u32) -> TestImage {
+        let mut img = make_single_color_img(length, width);
+        for l in block_l..(block_l + block_length) {
+            for w in block_w..(block_w + block_width) {
+                img.put_pixel(l, w, COLOR_2.value);
+            }
+        }
+
+        img
+    }
+
+    fn assert_bricks_cover_img(img: &TestImage, mosaic: &Mosaic<u8, TestBrick, TestColor>, height: u32) {
+        let mut covered = BTreeSet::new();
+        for brick in mosaic.iter() {
+            for l in brick.l..(brick.l + brick.brick.length() as u32) {
+                for w in brick.w..(brick.w + brick.brick.width() as u32) {
+                    for h in brick.h..(brick.h + brick.brick.height() as u32) {
+                        assert_eq!(img.pixel(l, w), brick.color.value);
+                        assert!(covered.insert((l, w, h)));
+                    }
+                }
+            }
+        }
+
+        assert_eq!((img.length() * img.width() * height) as usize, covered.len());
+    }
+
+    #[test]
+    fn test_merge_chunks_across_east_edge() {
+        let img = make_block_img(300, 10, 245, 2, 20, 4);
+        let (_, palette) = make_test_img();
+
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image(
+            &img,
+            &palette,
+            |_, _, _| 1,
+            |_, _, _, _| UNIT_BRICK
+        ).unwrap().reduce_bricks(&[TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, FOUR_BY_TWO_PLATE], &[]).unwrap();
+
+        assert_bricks_cover_img(&img, &mosaic, 1);
+        assert!(mosaic.iter().any(|brick| brick.color == COLOR_2 && brick.l < 255 && brick.l + brick.brick.length() as u32 > 255));
+        assert_eq!(10, mosaic.iter().filter(|brick| brick.color == COLOR_2).count());
+    }
+
+    #[test]
+    fn test_merge_chunks_across_south_edge() {
+        let img = make_block_img(10, 300, 3, 250, 4, 10);
+        let (_, palette) = make_test_img();
+
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image(
+            &img,
+            &palette,
+            |_, _, _| 3,
+            |_, _, _, _| UNIT_BRICK
+        ).unwrap().reduce_bricks(&[TWO_BY_TWO_PLATE], &[]).unwrap();
+
+        assert_bricks_cover_img(&img, &mosaic, 3);
+        assert!(mosaic.iter().any(|brick| brick.color == COLOR_2 && brick.w < 255 && brick.w + brick.brick.width() as u32 > 255));
+        assert_eq!(30, mosaic.iter().filter(|brick| brick.color == COLOR_2).count());
+    }
+
+    #[test]
+    fn test_merge_chunks_across_corner() {
+        let img = make_block_img(300, 300, 251, 251, 8, 8);
+        let (_, palette) = make_test_img();
+
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image(
+            &img,
+            &palette,
+            |_, _, _| 1,
+            |_, _, _, _| UNIT_BRICK
+        ).unwrap().reduce_bricks(&[TWO_BY_TWO_PLATE], &[]).unwrap();
+
+        assert_bricks_cover_img(&img, &mosaic, 1);
+        assert_eq!(16, mosaic.iter().filter(|brick| brick.color == COLOR_2).count());
+    }
+
+    #[test]
+    fn test_wide_regions_cross_section_seam() {
+        let img = make_single_color_img(300, 2);
+        let (_, palette) = make_test_img();
+
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image(
+            &img,
+            &palette,
+            |_, _, _| 2,
+            |_, _, _, _| UNIT_BRICK
+        ).unwrap().reduce_bricks(&[SIX_BY_ONE_PLATE], &[]).unwrap();
+
+        assert_bricks_cover_img(&img, &mosaic, 2);
+        assert!(mosaic.validate().is_empty());
+
+        // Some brick crosses the section edge in every row of every layer
+        for (w, h) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            assert!(mosaic.brick_at(254, w, h).is_some_and(|brick| brick.l + brick.brick.length() as u32 > 255));
+        }
+
+        // Layers made of different unit bricks form separate chunks, which are cut in different places
+        let layered: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image(
+            &img,
+            &palette,
+            |_, _, _| 2,
+            |_, _, h, _| match h {
+                0 => UNIT_BRICK,
+                _ => UNIT_BRICK_2
+            }
+        ).unwrap();
+        let chunk_starts = |h: u32| -> Vec<u32> {
+            let mut starts: Vec<u32> = layered.sections.iter()
+                .flat_map(|(l, _, section_h, chunks)| chunks.iter()
+                    .filter(move |chunk| section_h + chunk.h as u32 == h)
+                    .map(move |chunk| l + chunk.l as u32))
+                .collect();
+            starts.sort();
+            starts
+        };
+        assert_eq!(vec![0, 185], chunk_starts(0));
+        assert_eq!(vec![0, 115], chunk_starts(1));
+    }
+
+    struct TestTileSource {
+        img: TestImage,
+        tiles: Vec<(u32, u32, u32, u32)>
+    }
+
+    impl TileSource for TestTileSource {
+        fn tile(&mut self, l: u32, w: u32, length: u32, width: u32) -> Vec<RawColor> {
+            self.tiles.push((l, w, length, width));
+            (w..(w + width)).flat_map(|w| (l..(l + length)).map(move |l| (l, w)))
+                .map(|(l, w)| self.img.pixel(l, w))
+                .collect()
+        }
+
+        fn length(&self) -> u32 {
+            self.img.length()
+        }
+
+        fn width(&self) -> u32 {
+            self.img.width()
+        }
+    }
+
+    #[test]
+    fn test_tiles_match_image() {
+        let (_, palette) = make_test_img();
+        let size = 300;
+
+        let mut rng = ChaCha8Rng::seed_from_u64(1705276380);
+        let mut img = TestImage::new(size, size);
+        for l in 0..size {
+            for w in 0..size {
+                let color = match rng.gen_range(0..4) {
+                    0 => COLOR_1,
+                    1 => COLOR_2,
+                    2 => COLOR_3,
+                    _ => COLOR_4
+                };
+                img.put_pixel(l, w, color.value);
+            }
+        }
+
+        let height_fn = |l: u32, w: u32, _| (l + w) % 4;
+        let expected: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image(&img, &palette, height_fn, |_, _, _, _| UNIT_BRICK).unwrap();
+        let mut expected_bricks: Vec<_> = expected.iter().collect();
+        expected_bricks.sort_by_key(|brick| (brick.l, brick.w, brick.h));
+
+        let mut source = TestTileSource { img, tiles: Vec::new() };
+        let mut bricks = Vec::new();
+        for mosaic in Mosaic::from_tiles(&mut source, &palette, height_fn, |_, _, _, _| UNIT_BRICK) {
+            let mosaic: Mosaic<u8, TestBrick, TestColor> = mosaic.unwrap();
+            assert_eq!(size, mosaic.length());
+            assert_eq!(size, mosaic.width());
+            bricks.extend(mosaic.reduce_bricks(&[], &[]).unwrap().iter());
+        }
+        bricks.sort_by_key(|brick| (brick.l, brick.w, brick.h));
+
+        assert_eq!(expected_bricks, bricks);
+        assert_eq!(vec![(0, 0, 255, 255), (255, 0, 45, 255), (0, 255, 255, 45), (255, 255, 45, 45)], source.tiles);
+    }
+
+    #[test]
+    fn test_tiles_keep_seams_between_tiles() {
+        let (_, palette) = make_test_img();
+        let img = make_single_color_img(300, 2);
+        let crosses_tile_edge = |brick: &PlacedBrick<u8, TestBrick, TestColor>| brick.l < 255 && brick.l + brick.brick.length() as u32 > 255;
+
+        let whole: Vec<_> = Mosaic::from_image(&img, &palette, |_, _, _| 1, |_, _, _, _| UNIT_BRICK)
+            .unwrap()
+            .reduce_bricks(&[FOUR_BY_TWO_PLATE], &[])
+            .unwrap()
+            .iter()
+            .collect();
+
+        let mut source = TestTileSource { img, tiles: Vec::new() };
+        let mut tiled = Vec::new();
+        for mosaic in Mosaic::from_tiles(&mut source, &palette, |_, _, _| 1, |_, _, _, _| UNIT_BRICK) {
+            let mosaic: Mosaic<u8, TestBrick, TestColor> = mosaic.unwrap();
+            tiled.extend(mosaic.reduce_bricks(&[FOUR_BY_TWO_PLATE], &[]).unwrap().iter());
+        }
+
+        // Both cover the same points, but only the mosaic built from the whole image has bricks across the tile edge
+        let volume_of = |bricks: &[PlacedBrick<u8, TestBrick, TestColor>]| bricks.iter().fold(0, |total, brick| total + volume(brick.brick));
+        assert_eq!(volume_of(&whole), volume_of(&tiled));
+        assert!(whole.iter().any(crosses_tile_edge));
+        assert!(!tiled.iter().any(crosses_tile_edge));
+    }
+
+    #[test]
+    fn test_tiles_empty_image() {
+        let (_, palette) = make_test_img();
+        let mut source = TestTileSource { img: TestImage::new(0, 0), tiles: Vec::new() };
+
+        let mosaics: Vec<Result<Mosaic<u8, TestBrick, TestColor>, MosaicError>> = Mosaic::from_tiles(
+            &mut source,
+            &palette,
+            |_, _, _| 1,
+            |_, _, _, _| UNIT_BRICK
+        ).collect();
+
+        assert!(mosaics.is_empty());
+        assert!(source.tiles.is_empty());
+    }
+
+    struct ShortTileSource;
+
+    impl TileSource for ShortTileSource {
+        fn tile(&mut self, _: u32, _: u32, length: u32, width: u32) -> Vec<RawColor> {
+            vec![COLOR_1.value; (length * width - 1) as usize]
+        }
+
+        fn length(&self) -> u32 {
+            4
+        }
+
+        fn width(&self) -> u32 {
+            5
+        }
+    }
+
+    #[test]
+    fn test_tiles_too_small() {
+        let (_, palette) = make_test_img();
+
+        let mosaics: Vec<Result<Mosaic<u8, TestBrick, TestColor>, MosaicError>> = Mosaic::from_tiles(
+            &mut ShortTileSource,
+            &palette,
+            |_, _, _| 1,
+            |_, _, _, _| UNIT_BRICK
+        ).collect();
+
+        assert_eq!(vec![Err(MosaicError::TileTooSmall)], mosaics);
+    }
+
+    struct TestVoxelGrid {
+        length: u32,
+        width: u32,
+        height: u32,
+        voxels: BTreeSet<(u32, u32, u32)>
+    }
+
+    impl VoxelGrid<u8, TestColor> for TestVoxelGrid {
+        fn voxel(&self, l: u32, w: u32, h: u32) -> Option<(TestColor, u8)> {
+            match self.voxels.contains(&(l, w, h)) {
+                true => Some((COLOR_1, UNIT_BRICK)),
+                false => None
+            }
+        }
+
+        fn length(&self) -> u32 {
+            self.length
+        }
+
+        fn width(&self) -> u32 {
+            self.width
+        }
+
+        fn height(&self) -> u32 {
+            self.height
+        }
+    }
+
+    fn assert_bricks_fill_voxels(grid: &TestVoxelGrid, mosaic: &Mosaic<u8, TestBrick, TestColor>) {
+        let mut covered = BTreeSet::new();
+        for brick in mosaic.iter() {
+            for l in brick.l..(brick.l + brick.brick.length() as u32) {
+                for w in brick.w..(brick.w + brick.brick.width() as u32) {
+                    for h in brick.h..(brick.h + brick.brick.height() as u32) {
+                        assert!(covered.insert((l, w, h)));
+                    }
+                }
+            }
+        }
+
+        assert_eq!(grid.voxels, covered);
+    }
+
+    #[test]
+    fn test_voxels_empty_grid() {
+        let grid = TestVoxelGrid { length: 3, width: 3, height: 3, voxels: BTreeSet::new() };
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_voxels(&grid).unwrap();
+
+        assert_eq!(0, mosaic.iter().count());
+        assert_eq!(3, mosaic.length());
+        assert_eq!(3, mosaic.width());
+    }
+
+    #[test]
+    fn test_voxels_arch_overhang() {
+        let mut voxels = BTreeSet::new();
+        for h in 0..3 {
+            voxels.insert((0, 0, h));
+            voxels.insert((3, 0, h));
+        }
+        voxels.insert((1, 0, 2));
+        voxels.insert((2, 0, 2));
+        let grid = TestVoxelGrid { length: 4, width: 1, height: 3, voxels };
+
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_voxels(&grid).unwrap()
+            .reduce_bricks(&[TWO_BY_ONE_PLATE], &[]).unwrap();
+
+        assert_bricks_fill_voxels(&grid, &mosaic);
+        assert!(mosaic.iter().all(|brick| brick.h == 2 || brick.l == 0 || brick.l == 3));
+        assert_eq!(2, mosaic.iter().filter(|brick| brick.h == 2).count());
+    }
+
+    #[test]
+    fn test_voxels_hollow_cube() {
+        let mut voxels = BTreeSet::new();
+        for l in 0..6 {
+            for w in 0..6 {
+                for h in 0..6 {
+                    let is_inside = (1..5).contains(&l) && (1..5).contains(&w) && (1..5).contains(&h);
+                    if !is_inside {
+                        voxels.insert((l, w, h));
+                    }
+                }
+            }
+        }
+        let grid = TestVoxelGrid { length: 6, width: 6, height: 6, voxels };
+
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_voxels(&grid).unwrap()
+            .reduce_bricks(&[TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, FOUR_BY_TWO_PLATE], &[]).unwrap();
+
+        assert_bricks_fill_voxels(&grid, &mosaic);
+        assert!(mosaic.iter().count() < grid.voxels.len());
+    }
+
+    #[test]
+    fn test_voxels_across_sections() {
+        let mut voxels = BTreeSet::new();
+        for l in 0..300 {
+            voxels.insert((l, 0, l));
+            voxels.insert((l, 1, 299 - l));
+            voxels.insert((l, 2, 0));
+        }
+        let grid = TestVoxelGrid { length: 300, width: 3, height: 300, voxels };
+
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_voxels(&grid).unwrap()
+            .reduce_bricks(&[THREE_BY_ONE_PLATE], &[]).unwrap();
+
+        assert_bricks_fill_voxels(&grid, &mosaic);
+    }
+
+    fn make_queried_mosaic() -> Mosaic<u8, TestBrick<'static>, TestColor> {
+        let (_, palette) = make_test_img();
+        let size = 300;
+
+        let mut rng = ChaCha8Rng::seed_from_u64(1705276380);
+        let mut img = TestImage::new(size, size);
+        for l in 0..size {
+            for w in 0..size {
+                let color = match rng.gen_range(0..3) {
+                    0 => COLOR_1,
+                    1 => COLOR_2,
+                    _ => COLOR_3
+                };
+                img.put_pixel(l, w, color.value);
+            }
+        }
+
+        Mosaic::from_image(&img, &palette, |l, w, _| (l / 7 + w / 5) % 4, |_, _, _, _| UNIT_BRICK).unwrap()
+            .reduce_bricks(&[TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, FOUR_BY_TWO_PLATE], &[]).unwrap()
+    }
+
+    fn contains_point(brick: &PlacedBrick<u8, TestBrick, TestColor>, l: u32, w: u32, h: u32) -> bool {
+        (brick.l..(brick.l + brick.brick.length() as u32)).contains(&l)
+            && (brick.w..(brick.w + brick.brick.width() as u32)).contains(&w)
+            && (brick.h..(brick.h + brick.brick.height() as u32)).contains(&h)
+    }
+
+    #[test]
+    fn test_brick_at_matches_scan() {
+        let mosaic = make_queried_mosaic();
+
+        for (l, w) in [(0, 0), (3, 4), (254, 254), (255, 254), (254, 255), (299, 299), (100, 280)] {
+            for h in 0..5 {
+                let expected = mosaic.iter().find(|brick| contains_point(brick, l, w, h));
+                assert_eq!(expected, mosaic.brick_at(l, w, h));
+            }
+        }
+
+        assert_eq!(None, mosaic.brick_at(300, 0, 0));
+        assert_eq!(None, mosaic.brick_at(u32::MAX, u32::MAX, u32::MAX));
+    }
+
+    #[test]
+    fn test_index_only_visits_nearby_chunks() {
+        let mosaic = make_queried_mosaic();
+        let total_chunks: usize = mosaic.sections.iter().map(|(_, _, _, chunks)| chunks.len()).sum();
+
+        for (l, w) in [(0, 0), (130, 260), (299, 299)] {
+            let visited = mosaic.index.chunks_in(&(l..l + 1), &(w..w + 1));
+            assert!(visited.len() * 10 < total_chunks);
+
+            for (section_index, (section_l, section_w, _, chunks)) in mosaic.sections.iter().enumerate() {
+                for (chunk_index, chunk) in chunks.iter().enumerate() {
+                    let chunk_l = section_l + chunk.l as u32;
+                    let chunk_w = section_w + chunk.w as u32;
+                    if (chunk_l..chunk_l + chunk.length as u32).contains(&l) && (chunk_w..chunk_w + chunk.width as u32).contains(&w) {
+                        assert!(visited.contains(&(section_index, chunk_index)));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_iter_layer_matches_scan() {
+        let mosaic = make_queried_mosaic();
+
+        for h in 0..5 {
+            let expected: Vec<_> = mosaic.iter().filter(|brick| brick.h <= h && h < brick.h + brick.brick.height() as u32).collect();
+            assert_eq!(expected, mosaic.iter_layer(h).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_iter_region_matches_scan() {
+        let mosaic = make_queried_mosaic();
+
+        for (l, w) in [(0..10, 0..10), (250..260, 100..101), (290..400, 240..300), (5..5, 0..300)] {
+            let expected: Vec<_> = mosaic.iter()
+                .filter(|brick| brick.l < l.end && brick.l + brick.brick.length() as u32 > l.start
+                    && brick.w < w.end && brick.w + brick.brick.width() as u32 > w.start)
+                .collect();
+            assert_eq!(expected, mosaic.iter_region(l, w).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_top_color_at() {
+        let (img, palette) = make_test_img();
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image(
+            &img,
+            &palette,
+            |l, _, _| l,
+            |_, _, _, _| UNIT_BRICK
+        ).unwrap();
+
+        assert_eq!(None, mosaic.top_color_at(0, 0));
+        for l in 1..4 {
+            for w in 0..5 {
+                assert_eq!(Some(img.pixel(l, w)), mosaic.top_color_at(l, w).map(|color| color.value));
+            }
+        }
+        assert_eq!(None, mosaic.top_color_at(4, 0));
+    }
+
+    fn make_halves_img() -> TestImage {
+        let mut img = TestImage::new(8, 4);
+        for l in 0..8 {
+            for w in 0..4 {
+                img.put_pixel(l, w, match l < 4 {
+                    true => COLOR_1.value,
+                    false => COLOR_2.value
+                });
+            }
+        }
+
+        img
+    }
+
+    fn sorted_bricks(mosaic: &Mosaic<u8, TestBrick<'static>, TestColor>) -> Vec<PlacedBrick<u8, TestBrick<'static>, TestColor>> {
+        let mut bricks: Vec<_> = mosaic.iter().collect();
+        bricks.sort_by_key(|brick| (brick.l, brick.w, brick.h));
+        bricks
+    }
+
+    #[test]
+    fn test_edit_recolor_keeps_untouched_chunks() {
+        let img = make_halves_img();
+        let (_, palette) = make_test_img();
+        let bricks = [TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, FOUR_BY_TWO_PLATE];
+
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image(&img, &palette, |_, _, _| 2, |_, _, _, _| UNIT_BRICK).unwrap()
+            .reduce_bricks(&bricks, &[]).unwrap();
+        let untouched: Vec<_> = sorted_bricks(&mosaic).into_iter().filter(|brick| brick.color == COLOR_2).collect();
+
+        let edited = mosaic.edit(&[Edit::Recolor { l: 0..2, w: 0..2, color: COLOR_3 }], &bricks, &[]).unwrap();
+
+        assert_eq!(untouched, sorted_bricks(&edited).into_iter().filter(|brick| brick.color == COLOR_2).collect::<Vec<_>>());
+        for l in 0..8 {
+            for w in 0..4 {
+                let expected = match (l < 2 && w < 2, l < 4) {
+                    (true, _) => COLOR_3,
+                    (false, true) => COLOR_1,
+                    (false, false) => COLOR_2
+                };
+
+                for h in 0..2 {
+                    assert_eq!(expected, edited.brick_at(l, w, h).unwrap().color);
+                }
+            }
+        }
+        assert_eq!(8 * 4 * 2, edited.iter().map(|brick| volume(brick.brick)).sum::<u32>());
+        assert_eq!(2, edited.iter().filter(|brick| brick.color == COLOR_3).count());
+    }
+
+    #[test]
+    fn test_edit_set_color_of_one_stud() {
+        let img = make_halves_img();
+        let (_, palette) = make_test_img();
+
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image(&img, &palette, |_, _, _| 1, |_, _, _, _| UNIT_BRICK).unwrap()
+            .edit(&[Edit::SetColor { l: 5, w: 2, color: COLOR_4 }], &[TWO_BY_ONE_PLATE], &[]).unwrap();
+
+        assert_eq!(Some(COLOR_4), mosaic.top_color_at(5, 2));
+        assert_eq!(1, mosaic.iter().filter(|brick| brick.color == COLOR_4).count());
+        assert_eq!(Some(COLOR_2), mosaic.top_color_at(4, 2));
+        assert_eq!(Some(COLOR_1), mosaic.top_color_at(3, 2));
+    }
+
+    #[test]
+    fn test_edit_set_height() {
+        let img = make_halves_img();
+        let (_, palette) = make_test_img();
+
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image(&img, &palette, |_, _, _| 2, |_, _, _, _| UNIT_BRICK).unwrap()
+            .edit(&[
+                Edit::SetHeight { l: 1, w: 1, height: 5, brick: UNIT_BRICK_2, color: COLOR_3 },
+                Edit::SetHeight { l: 6, w: 3, height: 0, brick: UNIT_BRICK, color: COLOR_3 },
+                Edit::SetHeight { l: 8, w: 0, height: 3, brick: UNIT_BRICK, color: COLOR_3 }
+            ], &[TWO_BY_ONE_PLATE], &[]).unwrap();
+
+        assert_eq!(COLOR_1, mosaic.brick_at(1, 1, 1).unwrap().color);
+        assert_eq!(COLOR_3, mosaic.brick_at(1, 1, 2).unwrap().color);
+        assert_eq!(Brick::Unit(UNIT_BRICK_2), mosaic.brick_at(1, 1, 4).unwrap().brick);
+        assert_eq!(None, mosaic.brick_at(1, 1, 5));
+        assert_eq!(None, mosaic.top_color_at(6, 3));
+        assert_eq!(None, mosaic.top_color_at(8, 0));
+        assert_eq!(8 * 4 * 2 + 3 - 2, mosaic.iter().map(|brick| volume(brick.brick)).sum::<u32>());
+    }
+
+    #[test]
+    fn test_edit_replace_color() {
+        let img = make_halves_img();
+        let (_, palette) = make_test_img();
+        let bricks = [TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, FOUR_BY_TWO_PLATE];
+
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image(&img, &palette, |_, _, _| 1, |_, _, _, _| UNIT_BRICK).unwrap()
+            .reduce_bricks(&bricks, &[]).unwrap()
+            .edit(&[Edit::ReplaceColor { from: COLOR_1, to: COLOR_2 }], &bricks, &[]).unwrap();
+
+        assert!(mosaic.iter().all(|brick| brick.color == COLOR_2));
+        assert_eq!(8 * 4, mosaic.iter().map(|brick| volume(brick.brick)).sum::<u32>());
+    }
+
+    #[test]
+    fn test_edit_across_sections() {
+        let img = make_single_color_img(300, 2);
+        let (_, palette) = make_test_img();
+
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image(&img, &palette, |_, _, _| 1, |_, _, _, _| UNIT_BRICK).unwrap()
+            .edit(&[Edit::Recolor { l: 250..260, w: 0..2, color: COLOR_2 }], &[TWO_BY_TWO_PLATE], &[]).unwrap();
+
+        assert_bricks_cover_img(&make_block_img(300, 2, 250, 0, 10, 2), &mosaic, 1);
+        assert!(mosaic.iter().any(|brick| brick.color == COLOR_2 && brick.l == 254 && brick.brick.length() == 2));
+    }
+
+    fn make_transformed_mosaic() -> Mosaic<u8, TestBrick<'static>, TestColor> {
+        let (img, palette) = make_test_img();
+        Mosaic::from_image(&img, &palette, |l, _, _| l % 2 + 1, |_, _, _, _| UNIT_BRICK).unwrap()
+            .reduce_bricks(&[TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, THREE_BY_ONE_PLATE], &[]).unwrap()
+    }
+
+    fn assert_points_moved(original: &Mosaic<u8, TestBrick<'static>, TestColor>,
+                           transformed: &Mosaic<u8, TestBrick<'static>, TestColor>,
+                           point_fn: impl Fn(u32, u32, u32) -> (u32, u32, u32)) {
+        let mut volume_sum = 0;
+        for brick in original.iter() {
+            for l in brick.l..(brick.l + brick.brick.length() as u32) {
+                for w in brick.w..(brick.w + brick.brick.width() as u32) {
+                    for h in brick.h..(brick.h + brick.brick.height() as u32) {
+                        let (new_l, new_w, new_h) = point_fn(l, w, h);
+                        assert_eq!(brick.color, transformed.brick_at(new_l, new_w, new_h).unwrap().color);
+                        volume_sum += 1;
+                    }
+                }
+            }
+        }
+
+        assert_eq!(volume_sum, transformed.iter().map(|brick| volume(brick.brick)).sum::<u32>());
+    }
+
+    #[test]
+    fn test_rotate_90() {
+        let mosaic = make_transformed_mosaic();
+        let rotated = mosaic.clone().rotate_90();
+
+        assert_eq!(5, rotated.length());
+        assert_eq!(4, rotated.width());
+        assert_eq!(mosaic.iter().count(), rotated.iter().count());
+        assert_points_moved(&mosaic, &rotated, |l, w, h| (4 - w, l, h));
+
+        let rotation_counts = |mosaic: &Mosaic<u8, TestBrick<'static>, TestColor>, turns: u8| {
+            let mut counts: Vec<u8> = mosaic.iter().filter_map(|brick| match brick.brick {
+                Brick::NonUnit(brick) => Some((brick.rotation_count + turns) % 4),
+                Brick::Unit(_) => None
+            }).collect();
+            counts.sort();
+            counts
+        };
+        assert_eq!(rotation_counts(&mosaic, 1), rotation_counts(&rotated, 0));
+    }
+
+    #[test]
+    fn test_rotate_180_and_270() {
+        let mosaic = make_transformed_mosaic();
+
+        assert_points_moved(&mosaic, &mosaic.clone().rotate_180(), |l, w, h| (3 - l, 4 - w, h));
+        assert_points_moved(&mosaic, &mosaic.clone().rotate_270(), |l, w, h| (w, 3 - l, h));
+        assert_eq!(sorted_bricks(&mosaic), sorted_bricks(&mosaic.clone().rotate_180().rotate_180()));
+        assert_eq!(sorted_bricks(&mosaic), sorted_bricks(&mosaic.clone().rotate_90().rotate_270()));
+    }
+
+    #[test]
+    fn test_mirror() {
+        let mosaic = make_transformed_mosaic();
+
+        assert_points_moved(&mosaic, &mosaic.clone().mirror_l(), |l, w, h| (3 - l, w, h));
+        assert_points_moved(&mosaic, &mosaic.clone().mirror_w(), |l, w, h| (l, 4 - w, h));
+        assert_eq!(sorted_bricks(&mosaic), sorted_bricks(&mosaic.clone().mirror_l().mirror_l()));
+        assert_eq!(sorted_bricks(&mosaic), sorted_bricks(&mosaic.clone().mirror_w().mirror_w()));
+    }
+
+    #[test]
+    fn test_translate() {
+        let mosaic = make_transformed_mosaic();
+        let translated = mosaic.clone().translate(300, 2);
+
+        assert_eq!(304, translated.length());
+        assert_eq!(7, translated.width());
+        assert_points_moved(&mosaic, &translated, |l, w, h| (l + 300, w + 2, h));
+        assert_eq!(mosaic.iter().count(), translated.iter().count());
+    }
+
+    #[test]
+    fn test_crop_splits_crossing_bricks() {
+        let mosaic = make_transformed_mosaic();
+        let cropped = mosaic.clone().crop(1..3, 1..10);
+
+        assert_eq!(2, cropped.length());
+        assert_eq!(4, cropped.width());
+        for brick in cropped.iter() {
+            let original = mosaic.brick_at(brick.l + 1, brick.w + 1, brick.h).unwrap();
+            assert_eq!(original.color, brick.color);
+            assert!(brick.brick == original.brick || brick.brick == Brick::Unit(UNIT_BRICK));
+        }
+        assert_eq!(4 * (2 + 1), cropped.iter().map(|brick| volume(brick.brick)).sum::<u32>());
+        assert_eq!(0, mosaic.crop(5..10, 0..5).iter().count());
+    }
+
+    #[test]
+    fn test_paste_replaces_covered_points() {
+        let (img, palette) = make_test_img();
+        let base: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image(
+            &make_single_color_img(8, 8),
+            &palette,
+            |_, _, _| 1,
+            |_, _, _, _| UNIT_BRICK
+        ).unwrap().reduce_bricks(&[FOUR_BY_FOUR_PLATE], &[]).unwrap();
+        let piece: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image(&img, &palette, |_, _, _| 1, |_, _, _, _| UNIT_BRICK).unwrap()
+            .reduce_bricks(&[TWO_BY_ONE_PLATE], &[]).unwrap();
+
+        let pasted = base.paste(&piece, 2, 6);
+
+        assert_eq!(8, pasted.length());
+        assert_eq!(11, pasted.width());
+        assert_eq!(8 * 8 + 4 * 5 - 4 * 2, pasted.iter().map(|brick| volume(brick.brick)).sum::<u32>());
+        for l in 0..8 {
+            for w in 0..11 {
+                let expected = match ((2..6).contains(&l) && w >= 6, w < 8) {
+                    (true, _) => Some(img.pixel(l - 2, w - 6)),
+                    (false, true) => Some(COLOR_1.value),
+                    (false, false) => None
+                };
+                assert_eq!(expected, pasted.top_color_at(l, w).map(|color| color.value));
+            }
+        }
+        assert_eq!(2, pasted.iter().filter(|brick| brick.brick == Brick::NonUnit(FOUR_BY_FOUR_PLATE)).count());
+    }
+
+    #[test]
+    fn test_rotate_across_sections() {
+        let img = make_block_img(300, 3, 250, 0, 10, 3);
+        let (_, palette) = make_test_img();
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image(&img, &palette, |_, _, _| 1, |_, _, _, _| UNIT_BRICK).unwrap()
+            .reduce_bricks(&[THREE_BY_ONE_PLATE], &[]).unwrap();
+
+        let rotated = mosaic.clone().rotate_90();
+        assert_points_moved(&mosaic, &rotated, |l, w, h| (2 - w, l, h));
+        assert_eq!(mosaic.iter().count(), rotated.iter().count());
+    }
+
+    #[test]
+    fn test_w_set_ranges_across_words() {
+        let mut ws = WSet::default();
+        for w in 60..200 {
+            ws.insert(w);
+        }
+        ws.insert(255);
+
+        assert!(ws.contains_range(60, 200));
+        assert!(!ws.contains_range(59, 200));
+        assert!(!ws.contains_range(60, 201));
+        assert_eq!(Some(60), ws.first());
+
+        ws.remove_range(60, 128);
+        assert!(!ws.contains(127));
+        assert!(ws.contains(128));
+        assert_eq!(Some(128), ws.first());
+
+        ws.remove_range(128, 255);
+        assert_eq!(Some(255), ws.first());
+        ws.remove_range(255, 255);
+        assert!(ws.contains(255));
+        assert!(!ws.is_empty());
+    }
+
+    // The reduction as it was before chunk rows were stored as bitsets, kept to check that the output did not change
+    fn reduce_with_tree_sets(chunk: &Chunk<u8, TestBrick<'static>, TestColor>,
+                             sizes: &[VolumeSortedBrick<u8, TestBrick<'static>>]) -> Vec<ChunkPlacedBrick<u8, TestBrick<'static>>> {
+        let rows: Vec<BTreeSet<u8>> = chunk.ws_included.iter()
+            .map(|ws_included| (0..chunk.width).filter(|&w| ws_included.contains(w)).collect())
+            .collect();
+        let mut ws_included_by_h = vec![rows; chunk.height as usize];
+        let mut bricks = Vec::new();
+
+        let fits = |l: u8, w: u8, h: u8, size: &VolumeSortedBrick<u8, TestBrick>, ws_included_by_h: &[Vec<BTreeSet<u8>>]| {
+            if u8::MAX - size.height() < h || u8::MAX - size.length() < l || u8::MAX - size.width() < w {
+                return false;
+            }
+
+            let (max_l, max_w, max_h) = (l + size.length(), w + size.width(), h + size.height());
+            max_h as usize <= ws_included_by_h.len() && ws_included_by_h[h as usize..max_h as usize].iter().all(|ws_included_by_l|
+                max_l as usize <= ws_included_by_l.len() && ws_included_by_l[l as usize..max_l as usize].iter()
+                    .all(|ws_included| ws_included.range(w..max_w).count() == size.width() as usize)
+            )
+        };
+
+        for h in 0..chunk.height {
+            for l in 0..chunk.length {
+                while let Some(&w) = ws_included_by_h[h as usize][l as usize].first() {
+                    let size = sizes.iter().find(|size| fits(l, w, h, size, &ws_included_by_h)).unwrap();
+
+                    for ws_included_by_l in &mut ws_included_by_h[h as usize..(h + size.height()) as usize] {
+                        for ws_included in &mut ws_included_by_l[l as usize..(l + size.length()) as usize] {
+                            for cur_w in w..(w + size.width()) {
+                                ws_included.remove(&cur_w);
+                            }
+                        }
+                    }
+
+                    bricks.push(ChunkPlacedBrick { l, w, h, brick: size.brick });
+                }
+            }
+        }
+
+        bricks
+    }
+
+    #[test]
+    fn test_w_sets_reduce_like_tree_sets() {
+        let (_, palette) = make_test_img();
+        let size = 300;
+
+        let mut rng = ChaCha8Rng::seed_from_u64(1705276380);
+        let mut img = TestImage::new(size, size);
+        for l in 0..size {
+            for w in 0..size {
+                let color = match rng.gen_range(0..3) {
+                    0 => COLOR_1,
+                    1 => COLOR_2,
+                    _ => COLOR_3
+                };
+                img.put_pixel(l, w, color.value);
+            }
+        }
+
+        let heights: Vec<Vec<u32>> = (0..size).map(
+            |_| (0..size).map(|_| rng.gen_range(0..4)).collect()
+        ).collect();
+
+        let bricks = [TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, FOUR_BY_TWO_PLATE, THREE_BY_ONE_PLATE, TWO_BY_TWO_BY_TWO_BRICK];
+        let mut sizes = Vec::new();
+        for brick in bricks {
+            sizes.push(VolumeSortedBrick { brick: Brick::NonUnit(brick) });
+            if brick.length() != brick.width() {
+                sizes.push(VolumeSortedBrick { brick: Brick::NonUnit(brick.rotate_90()) });
+            }
+        }
+        sizes.push(VolumeSortedBrick { brick: Brick::Unit(UNIT_BRICK) });
+        sizes.sort();
+
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image(
+            &img,
+            &palette,
+            |l, w, _| heights[w as usize][l as usize],
+            |_, _, _, _| UNIT_BRICK
+        ).unwrap();
+        let expected: Vec<Vec<ChunkPlacedBrick<u8, TestBrick>>> = mosaic.sections.iter()
+            .flat_map(|(_, _, _, chunks)| chunks.iter().map(|chunk| reduce_with_tree_sets(chunk, &sizes)))
+            .collect();
+
+        let reduced = mosaic.reduce_bricks(&bricks, &[]).unwrap();
+        let actual: Vec<Vec<ChunkPlacedBrick<u8, TestBrick>>> = reduced.sections.into_iter()
+            .flat_map(|(_, _, _, chunks)| chunks.into_iter().map(|chunk| chunk.bricks))
+            .collect();
+
+        assert!(expected.iter().any(|bricks| bricks.iter().any(|brick| brick.brick.height() > 1)));
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn test_sections_and_chunks_keep_order() {
+        let (_, palette) = make_test_img();
+        let length = 600;
+        let width = 300;
+
+        let mut rng = ChaCha8Rng::seed_from_u64(1705276380);
+        let mut img = TestImage::new(length, width);
+        for l in 0..length {
+            for w in 0..width {
+                let color = match rng.gen_range(0..4) {
+                    0 => COLOR_1,
+                    1 => COLOR_2,
+                    2 => COLOR_3,
+                    _ => COLOR_4
+                };
+                img.put_pixel(l, w, color.value);
+            }
+        }
+
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image(
+            &img,
+            &palette,
+            |_, _, _| 2,
+            |_, _, _, _| UNIT_BRICK
+        ).unwrap();
+
+        let origins: Vec<(u32, u32, u32)> = mosaic.sections.iter().map(|(l, w, h, _)| (*l, *w, *h)).collect();
+        assert_eq!(vec![(0, 0, 0), (0, 255, 0), (255, 0, 0), (255, 255, 0), (510, 0, 0), (510, 255, 0)], origins);
+
+        let chunk_origins = |mosaic: &Mosaic<u8, TestBrick, TestColor>| -> Vec<(u8, u8, u8)> {
+            mosaic.sections.iter()
+                .flat_map(|(_, _, _, chunks)| chunks.iter().map(|chunk| (chunk.l, chunk.w, chunk.h)))
+                .collect()
+        };
+        let unreduced_origins = chunk_origins(&mosaic);
+        let reduced = mosaic.reduce_bricks(&[TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE], &[]).unwrap();
+        assert_eq!(unreduced_origins, chunk_origins(&reduced));
+    }
+
+    #[test]
+    #[cfg(not(feature = "parallel"))]
+    fn test_serial_build_accepts_thread_local_closures() {
+        let (img, palette) = make_test_img();
+        let calls = std::rc::Rc::new(std::cell::RefCell::new(0));
+
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image(
+            &img,
+            &palette,
+            |_, _, _| {
+                *calls.borrow_mut() += 1;
+                1
+            },
+            |_, _, _, _| UNIT_BRICK
+        ).unwrap();
+
+        assert_eq!(20, mosaic.iter().count());
+        assert!(*calls.borrow() > 0);
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn test_parallel_matches_serial() {
+        let (_, palette) = make_test_img();
+        let length = 600;
+        let width = 300;
+
+        let mut rng = ChaCha8Rng::seed_from_u64(1705276380);
+        let mut img = TestImage::new(length, width);
+        for l in 0..length {
+            for w in 0..width {
+                let color = match rng.gen_range(0..4) {
+                    0 => COLOR_1,
+                    1 => COLOR_2,
+                    2 => COLOR_3,
+                    _ => COLOR_4
+                };
+                img.put_pixel(l, w, color.value);
+            }
+        }
+
+        let build = || -> Mosaic<u8, TestBrick, TestColor> {
+            Mosaic::from_image(&img, &palette, |l, w, _| (l + w) % 4, |_, _, _, _| UNIT_BRICK)
+                .unwrap()
+                .reduce_bricks(&[TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, FOUR_BY_TWO_PLATE], &[])
+                .unwrap()
+        };
+
+        // A pool with one thread runs every task in order, just as the serial build does
+        let serial = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap().install(build);
+        let parallel = rayon::ThreadPoolBuilder::new().num_threads(4).build().unwrap().install(build);
+
+        assert_eq!(serial, parallel);
+        assert_eq!(serial.iter().collect::<Vec<_>>(), parallel.iter().collect::<Vec<_>>());
+    }
 }
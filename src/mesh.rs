@@ -0,0 +1,618 @@
+use std::collections::BTreeMap;
+use std::io::{ErrorKind, Read, Seek};
+use std::path::Path;
+use tobj::{LoadError, LoadOptions};
+use crate::{Color, Mosaic, MosaicError, NonUnitBrick, Palette, RawColor, ThreadSafe, UnitBrick, VoxelGrid};
+
+// Meshes do not agree on which axis points up, so the caller chooses how a mesh's x, y, and z
+// axes map onto the l, w, and h axes. Both mappings assume the mesh is right-handed, as in
+// most modeling programs:
+//
+//     UpAxis::Z: l = x, w = -y, h = z (x east, y north)
+//     UpAxis::Y: l = x, w = z,  h = y (x east, z south)
+//
+// The l, w, and h axes are left-handed, so both mappings mirror the mesh's handedness. This
+// only matters for the direction of face normals, which is accounted for below.
+
+// ====================
+// PUBLIC STRUCTS
+// ====================
+
+#[non_exhaustive]
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum MeshError {
+    Io(ErrorKind),
+    Obj(LoadError),
+    InvalidStudSize(f64),
+    GridTooLarge(u32, u32, u32),
+    Mosaic(MosaicError)
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum UpAxis {
+    Y,
+    Z
+}
+
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct Triangle {
+    pub vertices: [[f64; 3]; 3],
+    pub color: Option<RawColor>
+}
+
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Mesh {
+    triangles: Vec<Triangle>
+}
+
+impl Mesh {
+    pub fn new(triangles: Vec<Triangle>) -> Self {
+        Mesh { triangles }
+    }
+
+    pub fn read_stl(reader: &mut (impl Read + Seek)) -> Result<Self, MeshError> {
+        let mesh = stl_io::read_stl(reader).map_err(|err| MeshError::Io(err.kind()))?;
+
+        // STL files have no colors, so every triangle uses the default color
+        let triangles = mesh.faces.iter()
+            .map(|face| Triangle {
+                vertices: face.vertices.map(|index| mesh.vertices[index].0.map(f64::from)),
+                color: None
+            })
+            .collect();
+
+        Ok(Mesh { triangles })
+    }
+
+    pub fn read_obj(path: impl AsRef<Path>) -> Result<Self, MeshError> {
+        // Other features of tobj add more options, which should keep their defaults
+        #[allow(clippy::needless_update)]
+        let options = LoadOptions {
+            single_index: true,
+            triangulate: true,
+            ignore_points: true,
+            ignore_lines: true,
+            ..Default::default()
+        };
+        let (models, materials) = tobj::load_obj(path.as_ref(), &options).map_err(MeshError::Obj)?;
+        let materials = materials.map_err(MeshError::Obj)?;
+
+        let mut triangles = Vec::new();
+        for model in models {
+            let mesh = model.mesh;
+
+            // A triangle takes the diffuse color of its material, if it has one
+            let color = mesh.material_id
+                .and_then(|id| materials.get(id))
+                .and_then(|material| material.diffuse)
+                .map(|[red, green, blue]| RawColor {
+                    red: to_channel(f64::from(red)),
+                    green: to_channel(f64::from(green)),
+                    blue: to_channel(f64::from(blue)),
+                    alpha: u8::MAX
+                });
+
+            let position = |index: u32| {
+                let index = index as usize * 3;
+                [
+                    f64::from(mesh.positions[index]),
+                    f64::from(mesh.positions[index + 1]),
+                    f64::from(mesh.positions[index + 2])
+                ]
+            };
+
+            for face in mesh.indices.chunks_exact(3) {
+                triangles.push(Triangle {
+                    vertices: [position(face[0]), position(face[1]), position(face[2])],
+                    color
+                });
+            }
+        }
+
+        Ok(Mesh { triangles })
+    }
+
+    pub fn triangles(&self) -> &[Triangle] {
+        &self.triangles
+    }
+
+    pub fn to_mosaic<U: UnitBrick, B: NonUnitBrick<U>, C: Color>(&self,
+                                                                 up: UpAxis,
+                                                                 stud_size: f64,
+                                                                 palette: &impl Palette<C>,
+                                                                 default_color: RawColor,
+                                                                 brick_fn: impl Fn(u32, u32, u32, C) -> U + ThreadSafe) -> Result<Mosaic<U, B, C>, MeshError> {
+        if !stud_size.is_finite() || stud_size <= 0.0 {
+            return Err(MeshError::InvalidStudSize(stud_size));
+        }
+
+        /* A stud is 20 LDU wide, but a plate is only 8 LDU tall. Scale the mesh so that each
+           point in the grid is one stud wide and one plate tall. */
+        let plate_size = stud_size * PLATE_LDU / STUD_LDU;
+        let scale = [stud_size, stud_size, plate_size];
+
+        let mapped: Vec<([[f64; 3]; 3], RawColor)> = self.triangles.iter()
+            .map(|triangle| (triangle.vertices.map(|vertex| map_axes(vertex, up)), triangle.color.unwrap_or(default_color)))
+            .collect();
+
+        let mut min = [f64::INFINITY; 3];
+        let mut max = [f64::NEG_INFINITY; 3];
+        for vertex in mapped.iter().flat_map(|(vertices, _)| vertices.iter()) {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(vertex[axis]);
+                max[axis] = max[axis].max(vertex[axis]);
+            }
+        }
+
+        let triangles: Vec<([[f64; 3]; 3], RawColor)> = mapped.into_iter()
+            .map(|(vertices, color)| (vertices.map(|vertex| [0, 1, 2].map(|axis| (vertex[axis] - min[axis]) / scale[axis])), color))
+            .collect();
+
+        let size = match triangles.is_empty() {
+            true => [0; 3],
+            false => [0, 1, 2].map(|axis| (((max[axis] - min[axis]) / scale[axis] - SIZE_TOLERANCE).ceil() as u32).max(1))
+        };
+
+        // A tiny stud size would otherwise allocate a grid far larger than any model could use
+        let points = (size[0] as usize).checked_mul(size[1] as usize)
+            .and_then(|points| points.checked_mul(size[2] as usize))
+            .filter(|&points| points <= MAX_GRID_POINTS)
+            .ok_or(MeshError::GridTooLarge(size[0], size[1], size[2]))?;
+
+        let mut grid = RawGrid {
+            length: size[0],
+            width: size[1],
+            height: size[2],
+            colors: vec![None; points],
+            hits: BTreeMap::new()
+        };
+
+        for &(vertices, color) in &triangles {
+            grid.record_hits(vertices, color);
+        }
+        grid.fill_between_hits();
+
+        // Points on the surface take the color of the nearest triangle, even if they were filled
+        for &(vertices, color) in &triangles {
+            grid.fill_surface(vertices, color);
+        }
+
+        // Find the nearest palette color for each distinct color once
+        let mut nearest_colors = BTreeMap::new();
+        let colors = grid.colors.into_iter()
+            .map(|color| color.and_then(|color| *nearest_colors.entry(color).or_insert_with(|| palette.nearest(color))))
+            .collect();
+
+        Mosaic::from_voxels(&MappedGrid {
+            length: grid.length,
+            width: grid.width,
+            height: grid.height,
+            colors,
+            brick_fn
+        }).map_err(MeshError::Mosaic)
+    }
+}
+
+// ====================
+// PRIVATE CONSTANTS
+// ====================
+
+const STUD_LDU: f64 = 20.0;
+const PLATE_LDU: f64 = 8.0;
+
+// Each point takes a few bytes while the mesh is filled, so this keeps the grid to a few hundred megabytes
+const MAX_GRID_POINTS: usize = 1 << 26;
+
+// Rounding error should not add a layer to a mesh that is exactly a whole number of studs or plates
+const SIZE_TOLERANCE: f64 = 1e-6;
+
+// Rays are moved slightly off the center of each point so they do not pass exactly through shared edges
+const RAY_OFFSET_L: f64 = 1.3e-5;
+const RAY_OFFSET_W: f64 = 2.9e-5;
+
+// Samples on the surface are moved slightly inside the mesh so faces on grid lines fill the solid side
+const SURFACE_OFFSET: f64 = 1e-6;
+
+// ====================
+// PRIVATE FUNCTIONS
+// ====================
+
+fn to_channel(value: f64) -> u8 {
+    (value.clamp(0.0, 1.0) * u8::MAX as f64).round() as u8
+}
+
+fn map_axes([x, y, z]: [f64; 3], up: UpAxis) -> [f64; 3] {
+    match up {
+        UpAxis::Y => [x, z, y],
+        UpAxis::Z => [x, -y, z]
+    }
+}
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+fn length_of(a: [f64; 3]) -> f64 {
+    (a[0] * a[0] + a[1] * a[1] + a[2] * a[2]).sqrt()
+}
+
+// ====================
+// PRIVATE STRUCTS
+// ====================
+
+struct RawGrid {
+    length: u32,
+    width: u32,
+    height: u32,
+    colors: Vec<Option<RawColor>>,
+    hits: BTreeMap<(u32, u32), Vec<(f64, RawColor)>>
+}
+
+impl RawGrid {
+    fn index(&self, l: u32, w: u32, h: u32) -> usize {
+        (h as usize * self.width as usize + w as usize) * self.length as usize + l as usize
+    }
+
+    fn record_hits(&mut self, [a, b, c]: [[f64; 3]; 3], color: RawColor) {
+        let det = (b[0] - a[0]) * (c[1] - a[1]) - (c[0] - a[0]) * (b[1] - a[1]);
+
+        // Triangles seen edge-on from above never cross a vertical ray
+        if det.abs() < f64::EPSILON {
+            return;
+        }
+
+        let min_l = a[0].min(b[0]).min(c[0]).floor().max(0.0) as u32;
+        let max_l = (a[0].max(b[0]).max(c[0]).ceil() as u32).min(self.length);
+        let min_w = a[1].min(b[1]).min(c[1]).floor().max(0.0) as u32;
+        let max_w = (a[1].max(b[1]).max(c[1]).ceil() as u32).min(self.width);
+
+        for w in min_w..max_w {
+            for l in min_l..max_l {
+                let ray_l = l as f64 + 0.5 + RAY_OFFSET_L;
+                let ray_w = w as f64 + 0.5 + RAY_OFFSET_W;
+
+                let weight_b = ((ray_l - a[0]) * (c[1] - a[1]) - (c[0] - a[0]) * (ray_w - a[1])) / det;
+                let weight_c = ((b[0] - a[0]) * (ray_w - a[1]) - (ray_l - a[0]) * (b[1] - a[1])) / det;
+                let weight_a = 1.0 - weight_b - weight_c;
+
+                if weight_a < 0.0 || weight_b < 0.0 || weight_c < 0.0 {
+                    continue;
+                }
+
+                let h = weight_a * a[2] + weight_b * b[2] + weight_c * c[2];
+                self.hits.entry((l, w)).or_default().push((h, color));
+            }
+        }
+    }
+
+    fn fill_between_hits(&mut self) {
+        let hits = std::mem::take(&mut self.hits);
+
+        /* A vertical ray enters the mesh at every other hit and leaves it at the next one, so
+           the points between each pair of hits are inside. This fills closed meshes without
+           needing consistent face normals. */
+        for ((l, w), mut column_hits) in hits {
+            column_hits.sort_by(|(h1, _), (h2, _)| h1.total_cmp(h2));
+
+            for pair in column_hits.chunks_exact(2) {
+                let (bottom, bottom_color) = pair[0];
+                let (top, top_color) = pair[1];
+
+                let min_h = (bottom - 0.5).ceil().max(0.0) as u32;
+                let max_h = ((top - 0.5).floor() + 1.0).clamp(0.0, self.height as f64) as u32;
+
+                for h in min_h..max_h {
+
+                    // Points inside take the color of the nearer face
+                    let center = h as f64 + 0.5;
+                    let color = match center - bottom < top - center {
+                        true => bottom_color,
+                        false => top_color
+                    };
+
+                    let index = self.index(l, w, h);
+                    self.colors[index] = Some(color);
+                }
+            }
+        }
+    }
+
+    fn fill_surface(&mut self, [a, b, c]: [[f64; 3]; 3], color: RawColor) {
+        if self.colors.is_empty() {
+            return;
+        }
+
+        /* The axes are mirrored from the mesh's, so the cross product of the edges points into
+           the mesh instead of out of it */
+        let inward = cross(sub(b, a), sub(c, a));
+        let inward_length = length_of(inward);
+        let offset = match inward_length > 0.0 {
+            true => inward.map(|value| value / inward_length * SURFACE_OFFSET),
+            false => [0.0; 3]
+        };
+
+        /* Samples are also pulled slightly toward the center of the triangle, so edges that lie on
+           grid lines fill the points on the triangle's side of them */
+        let center = [0, 1, 2].map(|axis| (a[axis] + b[axis] + c[axis]) / 3.0);
+
+        // Sample the triangle densely enough that every point it passes through gets a sample
+        let longest_edge = length_of(sub(b, a)).max(length_of(sub(c, a))).max(length_of(sub(c, b)));
+        let steps = ((longest_edge * 2.0).ceil() as u32).max(1);
+
+        for i in 0..=steps {
+            for j in 0..=(steps - i) {
+                let weight_b = i as f64 / steps as f64;
+                let weight_c = j as f64 / steps as f64;
+                let point = [0, 1, 2].map(|axis| {
+                    let value = a[axis] + (b[axis] - a[axis]) * weight_b + (c[axis] - a[axis]) * weight_c;
+                    value + (center[axis] - value) * SURFACE_OFFSET + offset[axis]
+                });
+
+                let l = (point[0].max(0.0) as u32).min(self.length - 1);
+                let w = (point[1].max(0.0) as u32).min(self.width - 1);
+                let h = (point[2].max(0.0) as u32).min(self.height - 1);
+
+                let index = self.index(l, w, h);
+                self.colors[index] = Some(color);
+            }
+        }
+    }
+}
+
+struct MappedGrid<C, F> {
+    length: u32,
+    width: u32,
+    height: u32,
+    colors: Vec<Option<C>>,
+    brick_fn: F
+}
+
+impl<U, C: Copy, F: Fn(u32, u32, u32, C) -> U> VoxelGrid<U, C> for MappedGrid<C, F> {
+    fn voxel(&self, l: u32, w: u32, h: u32) -> Option<(C, U)> {
+        let color = self.colors[(h as usize * self.width as usize + w as usize) * self.length as usize + l as usize]?;
+        Some((color, (self.brick_fn)(l, w, h, color)))
+    }
+
+    fn length(&self) -> u32 {
+        self.length
+    }
+
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+}
+
+#[cfg(all(test, feature = "default", feature = "mesh"))]
+mod tests {
+    use std::collections::BTreeSet;
+    use std::io::Cursor;
+    use crate::{Mosaic, RawColor};
+    use crate::mesh::{Mesh, MeshError, Triangle, UpAxis};
+    use crate::palette::EuclideanDistancePalette;
+    use crate::tests::{COLOR_1, COLOR_2, TestBrick, TestColor, TWO_BY_TWO_PLATE, UNIT_BRICK};
+
+    type Vertices = [[f64; 3]; 3];
+
+    fn make_box(min: [f64; 3], max: [f64; 3]) -> Vec<Vertices> {
+        let [x0, y0, z0] = min;
+        let [x1, y1, z1] = max;
+
+        // Each face is listed counterclockwise when seen from outside the box
+        let faces = [
+            [[x0, y0, z0], [x0, y0, z1], [x0, y1, z1], [x0, y1, z0]],
+            [[x1, y0, z0], [x1, y1, z0], [x1, y1, z1], [x1, y0, z1]],
+            [[x0, y0, z0], [x1, y0, z0], [x1, y0, z1], [x0, y0, z1]],
+            [[x0, y1, z0], [x0, y1, z1], [x1, y1, z1], [x1, y1, z0]],
+            [[x0, y0, z0], [x0, y1, z0], [x1, y1, z0], [x1, y0, z0]],
+            [[x0, y0, z1], [x1, y0, z1], [x1, y1, z1], [x0, y1, z1]]
+        ];
+
+        faces.iter()
+            .flat_map(|[a, b, c, d]| [[*a, *b, *c], [*a, *c, *d]])
+            .collect()
+    }
+
+    fn make_mesh(boxes: &[(Vec<Vertices>, Option<RawColor>)]) -> Mesh {
+        Mesh::new(boxes.iter()
+            .flat_map(|(triangles, color)| triangles.iter().map(|&vertices| Triangle { vertices, color: *color }))
+            .collect())
+    }
+
+    fn voxels(mosaic: &Mosaic<u8, TestBrick, TestColor>) -> BTreeSet<(u32, u32, u32)> {
+        let mut voxels = BTreeSet::new();
+        for brick in mosaic.iter() {
+            for l in brick.l..(brick.l + brick.brick.length() as u32) {
+                for w in brick.w..(brick.w + brick.brick.width() as u32) {
+                    for h in brick.h..(brick.h + brick.brick.height() as u32) {
+                        assert!(voxels.insert((l, w, h)));
+                    }
+                }
+            }
+        }
+
+        voxels
+    }
+
+    fn make_mosaic(mesh: &Mesh, up: UpAxis) -> Mosaic<u8, TestBrick<'static>, TestColor> {
+        let palette = EuclideanDistancePalette::new(&[COLOR_1, COLOR_2]);
+        mesh.to_mosaic(up, 1.0, &palette, COLOR_1.into(), |_, _, _, _| UNIT_BRICK).unwrap()
+    }
+
+    #[test]
+    fn test_empty_mesh() {
+        let mosaic = make_mosaic(&Mesh::default(), UpAxis::Z);
+        assert_eq!(0, mosaic.iter().count());
+    }
+
+    #[test]
+    fn test_invalid_stud_size() {
+        let mesh = make_mesh(&[(make_box([0.0, 0.0, 0.0], [1.0, 1.0, 1.0]), None)]);
+        let palette = EuclideanDistancePalette::new(&[COLOR_1, COLOR_2]);
+
+        for stud_size in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let mosaic: Result<Mosaic<u8, TestBrick, TestColor>, MeshError> = mesh.to_mosaic(UpAxis::Z, stud_size, &palette, COLOR_1.into(), |_, _, _, _| UNIT_BRICK);
+            assert!(matches!(mosaic, Err(MeshError::InvalidStudSize(_))));
+        }
+    }
+
+    #[test]
+    fn test_grid_too_large() {
+        let mesh = make_mesh(&[(make_box([0.0, 0.0, 0.0], [1.0, 1.0, 1.0]), None)]);
+        let palette = EuclideanDistancePalette::new(&[COLOR_1, COLOR_2]);
+
+        let mosaic: Result<Mosaic<u8, TestBrick, TestColor>, MeshError> = mesh.to_mosaic(UpAxis::Z, 1e-3, &palette, COLOR_1.into(), |_, _, _, _| UNIT_BRICK);
+        assert_eq!(Err(MeshError::GridTooLarge(1000, 1000, 2500)), mosaic);
+    }
+
+    #[test]
+    fn test_solid_box_is_filled() {
+        let mesh = make_mesh(&[(make_box([0.0, 0.0, 0.0], [3.0, 3.0, 1.2]), None)]);
+        let mosaic = make_mosaic(&mesh, UpAxis::Z);
+
+        assert_eq!(3, mosaic.length());
+        assert_eq!(3, mosaic.width());
+        assert_eq!(27, voxels(&mosaic).len());
+    }
+
+    #[test]
+    fn test_plates_are_shorter_than_studs() {
+        let mesh = make_mesh(&[(make_box([0.0, 0.0, 0.0], [2.0, 1.0, 2.0]), None)]);
+        let mosaic = make_mosaic(&mesh, UpAxis::Z);
+
+        let voxels = voxels(&mosaic);
+        assert_eq!(10, voxels.len());
+        assert_eq!(Some(4), voxels.iter().map(|&(_, _, h)| h).max());
+    }
+
+    #[test]
+    fn test_cavity_stays_empty() {
+        let mut inner = make_box([2.0, 2.0, 0.8], [4.0, 4.0, 1.6]);
+
+        // The inner surface faces into the cavity, so its faces are wound the other way
+        for triangle in inner.iter_mut() {
+            triangle.swap(1, 2);
+        }
+
+        let mesh = make_mesh(&[(make_box([0.0, 0.0, 0.0], [6.0, 6.0, 2.4]), None), (inner, None)]);
+        let voxels = voxels(&make_mosaic(&mesh, UpAxis::Z));
+
+        assert_eq!(6 * 6 * 6 - 2 * 2 * 2, voxels.len());
+        assert!(!voxels.contains(&(2, 2, 2)));
+        assert!(!voxels.contains(&(3, 3, 3)));
+        assert!(voxels.contains(&(1, 2, 2)));
+        assert!(voxels.contains(&(4, 2, 2)));
+    }
+
+    #[test]
+    fn test_up_axis_mapping() {
+        let tall_in_y = make_mesh(&[(make_box([0.0, 0.0, 0.0], [1.0, 2.0, 3.0]), None)]);
+
+        let y_up = make_mosaic(&tall_in_y, UpAxis::Y);
+        assert_eq!(1, y_up.length());
+        assert_eq!(3, y_up.width());
+        assert_eq!(Some(4), voxels(&y_up).iter().map(|&(_, _, h)| h).max());
+
+        let z_up = make_mosaic(&tall_in_y, UpAxis::Z);
+        assert_eq!(1, z_up.length());
+        assert_eq!(2, z_up.width());
+        assert_eq!(Some(7), voxels(&z_up).iter().map(|&(_, _, h)| h).max());
+    }
+
+    #[test]
+    fn test_north_is_up_with_z_up() {
+        let mesh = make_mesh(&[
+            (make_box([0.0, 0.0, 0.0], [1.0, 1.0, 0.4]), Some(COLOR_2.into())),
+            (make_box([0.0, 1.0, 0.0], [1.0, 2.0, 0.4]), Some(COLOR_1.into()))
+        ]);
+        let mosaic = make_mosaic(&mesh, UpAxis::Z);
+
+        // The box with the larger y is further north, so it has the smaller w
+        let south_brick = mosaic.iter().find(|brick| brick.w == 1).unwrap();
+        assert_eq!(COLOR_2, south_brick.color);
+    }
+
+    #[test]
+    fn test_reduce_voxelized_mesh() {
+        let mesh = make_mesh(&[(make_box([0.0, 0.0, 0.0], [4.0, 4.0, 0.8]), Some(COLOR_2.into()))]);
+        let mosaic = make_mosaic(&mesh, UpAxis::Z).reduce_bricks(&[TWO_BY_TWO_PLATE], &[]).unwrap();
+
+        assert_eq!(8, mosaic.iter().count());
+        assert!(mosaic.iter().all(|brick| brick.color == COLOR_2));
+    }
+
+    #[test]
+    fn test_read_ascii_stl() {
+        let mut stl = String::from("solid test\n");
+        for [a, b, c] in make_box([0.0, 0.0, 0.0], [2.0, 2.0, 0.8]) {
+            stl.push_str("facet normal 0 0 0\nouter loop\n");
+            for [x, y, z] in [a, b, c] {
+                stl.push_str(&format!("vertex {} {} {}\n", x, y, z));
+            }
+            stl.push_str("endloop\nendfacet\n");
+        }
+        stl.push_str("endsolid test\n");
+
+        let mesh = Mesh::read_stl(&mut Cursor::new(stl.into_bytes())).unwrap();
+        assert_eq!(12, mesh.triangles().len());
+        assert_eq!(8, voxels(&make_mosaic(&mesh, UpAxis::Z)).len());
+    }
+
+    #[test]
+    fn test_read_binary_stl() {
+        let triangles: Vec<stl_io::Triangle> = make_box([0.0, 0.0, 0.0], [1.0, 3.0, 0.4]).iter()
+            .map(|vertices| stl_io::Triangle {
+                normal: stl_io::Vector::new([0.0, 0.0, 0.0]),
+                vertices: vertices.map(|vertex| stl_io::Vector::new(vertex.map(|value| value as f32)))
+            })
+            .collect();
+        let mut bytes = Vec::new();
+        stl_io::write_stl(&mut bytes, triangles.iter()).unwrap();
+
+        let mesh = Mesh::read_stl(&mut Cursor::new(bytes)).unwrap();
+        assert_eq!(12, mesh.triangles().len());
+        assert_eq!(3, voxels(&make_mosaic(&mesh, UpAxis::Z)).len());
+    }
+
+    #[test]
+    fn test_read_obj_with_materials() {
+        let dir = std::env::temp_dir().join(format!("lego-mosaic-obj-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut obj = String::from("mtllib colors.mtl\n");
+        let mut vertex_count = 0;
+        for (name, min, max) in [("first", [0.0, 0.0, 0.0], [2.0, 0.8, 1.0]), ("second", [2.0, 0.0, 0.0], [4.0, 0.8, 1.0])] {
+            obj.push_str(&format!("o {}\nusemtl {}\n", name, name));
+            for [a, b, c] in make_box(min, max) {
+                for [x, y, z] in [a, b, c] {
+                    obj.push_str(&format!("v {} {} {}\n", x, y, z));
+                }
+                obj.push_str(&format!("f {} {} {}\n", vertex_count + 1, vertex_count + 2, vertex_count + 3));
+                vertex_count += 3;
+            }
+        }
+
+        let mtl = "newmtl first\nKd 0.92 0.25 0.2\nnewmtl second\nKd 0.92 0.91 0.2\n";
+        std::fs::write(dir.join("colors.mtl"), mtl).unwrap();
+        std::fs::write(dir.join("model.obj"), obj).unwrap();
+
+        let mesh = Mesh::read_obj(dir.join("model.obj"));
+        std::fs::remove_dir_all(&dir).unwrap();
+        let mesh = mesh.unwrap();
+
+        assert_eq!(24, mesh.triangles().len());
+        let mosaic = make_mosaic(&mesh, UpAxis::Y);
+        assert_eq!(4 * 2, mosaic.iter().count());
+        assert!(mosaic.iter().all(|brick| match brick.l < 2 {
+            true => brick.color == COLOR_1,
+            false => brick.color == COLOR_2
+        }));
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::{BTreeMap, BTreeSet};
+use crate::{points_in_box, Base, BillOfMaterials, Color, Frame, Mosaic, NonUnitBrick, PlacedBrick, UnitBrick};
+
+// ====================
+// PUBLIC TYPE ALIASES
+// ====================
+
+pub type Overlap<U, B, C> = (PlacedBrick<U, B, C>, PlacedBrick<U, B, C>);
+
+// ====================
+// PUBLIC STRUCTS
+// ====================
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct Model<U, B, C> {
+    bricks: Vec<PlacedBrick<U, B, C>>,
+    length: u32,
+    width: u32,
+    height: u32
+}
+
+impl<U: UnitBrick, B: NonUnitBrick<U>, C: Color> Model<U, B, C> {
+    pub fn new() -> Self {
+        Model { bricks: Vec::new(), length: 0, width: 0, height: 0 }
+    }
+
+    // The mosaic sits on top of the base, which is where write_base and write_mosaic are usually placed by hand
+    pub fn from_base_and_mosaic(base: &Base<U, B, C>, mosaic: &Mosaic<U, B, C>) -> Self {
+        let mut model = Model::new();
+        model.add_base(base, 0, 0, 0);
+        model.add_mosaic(mosaic, 0, 0, base.height());
+        model
+    }
+
+    /* The frame is as long and as wide as the base and sits on its outer ring, while the mosaic
+       sits on the base inside the frame, offset by the frame's border */
+    pub fn from_base_frame_and_mosaic(base: &Base<U, B, C>, frame: &Frame<U, B, C>, mosaic: &Mosaic<U, B, C>) -> Self {
+        let mut model = Model::new();
+        model.add_base(base, 0, 0, 0);
+        model.add_frame(frame, 0, 0, base.height());
+        model.add_mosaic(mosaic, frame.border(), frame.border(), base.height());
+        model
+    }
+
+    pub fn add_base(&mut self, base: &Base<U, B, C>, l: u32, w: u32, h: u32) {
+        self.add_bricks(base.iter(), l, w, h);
+    }
+
+    pub fn add_mosaic(&mut self, mosaic: &Mosaic<U, B, C>, l: u32, w: u32, h: u32) {
+        self.add_bricks(mosaic.iter(), l, w, h);
+    }
+
+    pub fn add_frame(&mut self, frame: &Frame<U, B, C>, l: u32, w: u32, h: u32) {
+        self.add_bricks(frame.iter(), l, w, h);
+    }
+
+    pub fn add_bricks(&mut self, bricks: impl IntoIterator<Item=PlacedBrick<U, B, C>>, l: u32, w: u32, h: u32) {
+        for brick in bricks {
+            let brick = PlacedBrick { l: brick.l + l, w: brick.w + w, h: brick.h + h, ..brick };
+
+            self.length = self.length.max(brick.l + brick.brick.length() as u32);
+            self.width = self.width.max(brick.w + brick.brick.width() as u32);
+            self.height = self.height.max(brick.h + brick.brick.height() as u32);
+            self.bricks.push(brick);
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item=PlacedBrick<U, B, C>> + '_ {
+        self.bricks.iter().copied()
+    }
+
+    // Each pair of bricks that share at least one point is returned once, in the order the bricks were added
+    pub fn overlaps(&self) -> Vec<Overlap<U, B, C>> {
+        let mut owners: BTreeMap<_, Vec<usize>> = BTreeMap::new();
+        let mut pairs = BTreeSet::new();
+
+        // Every earlier owner of a point is kept, so a point shared by three bricks gives all three pairs
+        for (index, brick) in self.bricks.iter().enumerate() {
+            let (length, width, height) = (brick.brick.length() as u32, brick.brick.width() as u32, brick.brick.height() as u32);
+            for point in points_in_box(brick.l, brick.w, brick.h, length, width, height) {
+                let point_owners = owners.entry(point).or_default();
+                pairs.extend(point_owners.iter().map(|&owner| (owner, index)));
+                point_owners.push(index);
+            }
+        }
+
+        pairs.into_iter()
+            .map(|(first, second)| (self.bricks[first], self.bricks[second]))
+            .collect()
+    }
+
+    pub fn top_color_at(&self, l: u32, w: u32) -> Option<C> {
+        self.bricks.iter()
+            .filter(|brick| l >= brick.l && l < brick.l + brick.brick.length() as u32
+                && w >= brick.w && w < brick.w + brick.brick.width() as u32)
+            .max_by_key(|brick| brick.h + brick.brick.height() as u32)
+            .map(|brick| brick.color)
+    }
+
+    pub fn bill_of_materials(&self) -> BillOfMaterials<U, B, C> {
+        BillOfMaterials::new(self.iter())
+    }
+
+    pub fn length(&self) -> u32 {
+        self.length
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+}
+
+#[cfg(all(test, feature = "default"))]
+mod tests {
+    use crate::{Base, Brick, Frame, FrameCorner, Model, Mosaic, NonUnitBrick, PlacedBrick};
+    use crate::tests::{COLOR_1, COLOR_2, make_single_color_img, make_test_img, TestBrick, TestColor, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, UNIT_BRICK};
+
+    fn make_base(length: u32, width: u32) -> Base<u8, TestBrick<'static>, TestColor> {
+        Base::new(length, width, COLOR_1, UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, &[]).unwrap()
+    }
+
+    fn make_mosaic(length: u32, width: u32) -> Mosaic<u8, TestBrick<'static>, TestColor> {
+        let (_, palette) = make_test_img();
+        Mosaic::from_image(&make_single_color_img(length, width), &palette, |_, _, _| 1, |_, _, _, _| UNIT_BRICK)
+            .unwrap()
+            .reduce_bricks(&[TWO_BY_TWO_PLATE], &[])
+            .unwrap()
+    }
+
+    #[test]
+    fn test_mosaic_sits_on_base() {
+        let base = make_base(4, 4);
+        let mosaic = make_mosaic(4, 4);
+
+        let model = Model::from_base_and_mosaic(&base, &mosaic);
+
+        assert_eq!((4, 4, base.height() + 1), (model.length(), model.width(), model.height()));
+        assert_eq!(base.iter().count() + mosaic.iter().count(), model.iter().count());
+        assert!(model.iter().filter(|brick| brick.h >= base.height()).count() == mosaic.iter().count());
+        assert!(model.overlaps().is_empty());
+        assert_eq!(mosaic.top_color_at(1, 2), model.top_color_at(1, 2));
+        assert_eq!(None, model.top_color_at(4, 0));
+    }
+
+    #[test]
+    fn test_mosaic_sits_inside_frame() {
+        let frame = Frame::new(4, 4, &[(1, 2)], FrameCorner::Butt, COLOR_2, UNIT_BRICK, &[TWO_BY_ONE_PLATE]).unwrap();
+        let base = make_base(frame.length(), frame.width());
+        let mosaic = make_mosaic(4, 4);
+
+        let model = Model::from_base_frame_and_mosaic(&base, &frame, &mosaic);
+
+        assert_eq!((6, 6, base.height() + 2), (model.length(), model.width(), model.height()));
+        assert!(model.overlaps().is_empty());
+        assert_eq!(Some(COLOR_2), model.top_color_at(0, 3));
+        assert_eq!(mosaic.top_color_at(0, 0), model.top_color_at(1, 1));
+
+        let bill = model.bill_of_materials();
+        assert_eq!(model.iter().count() as u32, bill.total());
+    }
+
+    #[test]
+    fn test_loose_bricks_are_offset() {
+        let mut model: Model<u8, TestBrick, TestColor> = Model::new();
+        model.add_bricks(vec![
+            PlacedBrick { l: 0, w: 0, h: 0, brick: Brick::NonUnit(TWO_BY_TWO_PLATE), color: COLOR_1 },
+            PlacedBrick { l: 2, w: 0, h: 0, brick: Brick::Unit(UNIT_BRICK), color: COLOR_2 }
+        ], 3, 1, 2);
+
+        assert_eq!((6, 3, 3), (model.length(), model.width(), model.height()));
+        assert!(model.iter().all(|brick| brick.l >= 3 && brick.w == 1 && brick.h == 2));
+        assert_eq!(Some(COLOR_2), model.top_color_at(5, 1));
+        assert_eq!(None, model.top_color_at(0, 0));
+    }
+
+    #[test]
+    fn test_overlapping_placements() {
+        let base = make_base(4, 4);
+        let mut model = Model::from_base_and_mosaic(&base, &make_mosaic(4, 4));
+        model.add_mosaic(&make_mosaic(2, 2), 3, 3, base.height());
+
+        let overlaps = model.overlaps();
+
+        assert!(!overlaps.is_empty());
+        assert!(overlaps.iter().all(|(first, second)| first.h == base.height() && second.h == base.height()));
+        assert!(overlaps.iter().any(|(_, second)| (second.l, second.w) == (3, 3)));
+        assert_eq!((5, 5), (model.length(), model.width()));
+    }
+
+    #[test]
+    fn test_three_bricks_at_same_point() {
+        let mut model: Model<u8, TestBrick, TestColor> = Model::new();
+        model.add_bricks(vec![
+            PlacedBrick { l: 0, w: 0, h: 0, brick: Brick::NonUnit(TWO_BY_TWO_PLATE), color: COLOR_1 },
+            PlacedBrick { l: 1, w: 1, h: 0, brick: Brick::Unit(UNIT_BRICK), color: COLOR_2 },
+            PlacedBrick { l: 1, w: 0, h: 0, brick: Brick::NonUnit(TWO_BY_ONE_PLATE.rotate_90()), color: COLOR_1 }
+        ], 0, 0, 0);
+
+        let pairs: Vec<((u32, u32), (u32, u32))> = model.overlaps().iter()
+            .map(|(first, second)| ((first.l, first.w), (second.l, second.w)))
+            .collect();
+
+        assert_eq!(vec![((0, 0), (1, 1)), ((0, 0), (1, 0)), ((1, 1), (1, 0))], pairs);
+    }
+}
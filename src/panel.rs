@@ -0,0 +1,283 @@
+use crate::{Base, BaseError, Brick, Color, Mosaic, MosaicError, NonUnitBrick, PlacedBrick, UnitBrick};
+
+// ====================
+// PUBLIC STRUCTS
+// ====================
+
+#[non_exhaustive]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum PanelError<B> {
+    ZeroPanelSize,
+    Mosaic(MosaicError),
+    Base(BaseError<B>)
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct BomEntry<U, B, C> {
+    pub brick: Brick<U, B>,
+    pub color: C,
+    pub count: u32
+}
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct BillOfMaterials<U, B, C> {
+    entries: Vec<BomEntry<U, B, C>>
+}
+
+impl<U: UnitBrick, B: NonUnitBrick<U>, C: Color> BillOfMaterials<U, B, C> {
+    pub fn new(bricks: impl IntoIterator<Item=PlacedBrick<U, B, C>>) -> Self {
+        let mut bill = BillOfMaterials { entries: Vec::new() };
+
+        for brick in bricks {
+            bill.add(brick.brick, brick.color, 1);
+        }
+
+        bill
+    }
+
+    pub fn add(&mut self, brick: Brick<U, B>, color: C, count: u32) {
+        match self.entries.iter_mut().find(|entry| entry.color == color && is_same_part(entry.brick, brick)) {
+            Some(entry) => entry.count += count,
+            None => self.entries.push(BomEntry { brick, color, count })
+        }
+    }
+
+    pub fn merge(&mut self, other: &BillOfMaterials<U, B, C>) {
+        for entry in &other.entries {
+            self.add(entry.brick, entry.color, entry.count);
+        }
+    }
+
+    pub fn entries(&self) -> &[BomEntry<U, B, C>] {
+        &self.entries
+    }
+
+    pub fn count(&self, brick: Brick<U, B>, color: C) -> u32 {
+        self.entries.iter()
+            .filter(|entry| entry.color == color && is_same_part(entry.brick, brick))
+            .map(|entry| entry.count)
+            .sum()
+    }
+
+    pub fn total(&self) -> u32 {
+        self.entries.iter().map(|entry| entry.count).sum()
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Panel<U, B, C> {
+    column: u32,
+    row: u32,
+    l: u32,
+    w: u32,
+    mosaic: Mosaic<U, B, C>,
+    base: Base<U, B, C>
+}
+
+impl<U: UnitBrick, B: NonUnitBrick<U>, C: Color> Panel<U, B, C> {
+    pub fn column(&self) -> u32 {
+        self.column
+    }
+
+    pub fn row(&self) -> u32 {
+        self.row
+    }
+
+    // Columns are lettered like spreadsheet columns (A to Z, then AA), while rows are numbered from 1
+    pub fn label(&self) -> String {
+        let mut letters = Vec::new();
+        let mut column = self.column + 1;
+
+        while column > 0 {
+            column -= 1;
+            letters.push((b'A' + (column % 26) as u8) as char);
+            column /= 26;
+        }
+
+        letters.iter().rev().collect::<String>() + &(self.row + 1).to_string()
+    }
+
+    pub fn l(&self) -> u32 {
+        self.l
+    }
+
+    pub fn w(&self) -> u32 {
+        self.w
+    }
+
+    pub fn mosaic(&self) -> &Mosaic<U, B, C> {
+        &self.mosaic
+    }
+
+    pub fn base(&self) -> &Base<U, B, C> {
+        &self.base
+    }
+
+    pub fn bill_of_materials(&self) -> BillOfMaterials<U, B, C> {
+        BillOfMaterials::new(self.base.iter().chain(self.mosaic.iter()))
+    }
+}
+
+impl<U: UnitBrick, B: NonUnitBrick<U>, C: Color> Mosaic<U, B, C> {
+    pub fn panels<F>(&self,
+                     panel_length: u32,
+                     panel_width: u32,
+                     bricks: &[B],
+                     exclusions: &[(B, C)],
+                     base_fn: F) -> Result<Vec<Panel<U, B, C>>, PanelError<B>>
+        where F: Fn(u32, u32) -> Result<Base<U, B, C>, BaseError<B>> {
+        if panel_length == 0 || panel_width == 0 {
+            return Err(PanelError::ZeroPanelSize);
+        }
+
+        let mut panels = Vec::new();
+
+        /* Panels along the east and south edges are smaller when the mosaic's size is not a
+           multiple of the panel size. Each panel is cropped from the mosaic and reduced again,
+           so no brick can cross from one panel into another. */
+        for (row, w) in (0..self.width()).step_by(panel_width as usize).enumerate() {
+            for (column, l) in (0..self.length()).step_by(panel_length as usize).enumerate() {
+                let l_range = l..(l + panel_length).min(self.length());
+                let w_range = w..(w + panel_width).min(self.width());
+
+                let mosaic = self.region(l_range.clone(), w_range.clone())
+                    .crop(l_range.clone(), w_range.clone())
+                    .reduce_bricks(bricks, exclusions)
+                    .map_err(PanelError::Mosaic)?;
+                let base = base_fn(l_range.len() as u32, w_range.len() as u32).map_err(PanelError::Base)?;
+
+                panels.push(Panel { column: column as u32, row: row as u32, l, w, mosaic, base });
+            }
+        }
+
+        Ok(panels)
+    }
+}
+
+// ====================
+// PRIVATE FUNCTIONS
+// ====================
+
+fn is_same_part<U: UnitBrick, B: NonUnitBrick<U>>(brick: Brick<U, B>, other: Brick<U, B>) -> bool {
+    match (brick, other) {
+        (Brick::Unit(brick), Brick::Unit(other)) => brick == other,
+        (Brick::NonUnit(brick), Brick::NonUnit(other)) => brick == other || brick.is_rotation_of(&other),
+        _ => false
+    }
+}
+
+#[cfg(all(test, feature = "default"))]
+mod tests {
+    use crate::{Base, BillOfMaterials, Brick, Mosaic, NonUnitBrick, PanelError, PlacedBrick};
+    use crate::tests::{COLOR_1, COLOR_2, make_single_color_img, make_test_img, TestBrick, TestColor, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, UNIT_BRICK};
+
+    fn make_base(length: u32, width: u32) -> Result<Base<u8, TestBrick<'static>, TestColor>, crate::BaseError<TestBrick<'static>>> {
+        Base::new(length, width, COLOR_1, UNIT_BRICK, TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE, &[])
+    }
+
+    #[test]
+    fn test_panels_cover_mosaic_without_crossing_edges() {
+        let (_, palette) = make_test_img();
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image(
+            &make_single_color_img(10, 7),
+            &palette,
+            |_, _, _| 1,
+            |_, _, _, _| UNIT_BRICK
+        ).unwrap().reduce_bricks(&[TWO_BY_TWO_PLATE], &[]).unwrap();
+
+        let panels = mosaic.panels(4, 4, &[TWO_BY_ONE_PLATE, TWO_BY_TWO_PLATE], &[], make_base).unwrap();
+
+        let labels: Vec<String> = panels.iter().map(|panel| panel.label()).collect();
+        assert_eq!(vec!["A1", "B1", "C1", "A2", "B2", "C2"], labels);
+
+        let mut area = 0;
+        for panel in &panels {
+            let (length, width) = (panel.mosaic().length(), panel.mosaic().width());
+            assert_eq!((length, width), (panel.base().length(), panel.base().width()));
+            assert_eq!(((10 - panel.l()).min(4), (7 - panel.w()).min(4)), (length, width));
+
+            for brick in panel.mosaic().iter() {
+                assert!(brick.l + brick.brick.length() as u32 <= length);
+                assert!(brick.w + brick.brick.width() as u32 <= width);
+            }
+            area += panel.mosaic().iter().map(|brick| brick.brick.length() as u32 * brick.brick.width() as u32).sum::<u32>();
+        }
+        assert_eq!(10 * 7, area);
+    }
+
+    #[test]
+    fn test_panel_keeps_colors() {
+        let (img, palette) = make_test_img();
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image(&img, &palette, |_, _, _| 1, |_, _, _, _| UNIT_BRICK).unwrap();
+
+        let panels = mosaic.panels(3, 2, &[TWO_BY_ONE_PLATE], &[], make_base).unwrap();
+        let panel = &panels[3];
+
+        assert_eq!("B2", panel.label());
+        assert_eq!((3, 2), (panel.l(), panel.w()));
+        assert_eq!(Some(COLOR_2), panel.mosaic().top_color_at(0, 0));
+        assert_eq!(mosaic.top_color_at(3, 3), panel.mosaic().top_color_at(0, 1));
+    }
+
+    #[test]
+    fn test_panel_bill_of_materials() {
+        let (_, palette) = make_test_img();
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image(
+            &make_single_color_img(4, 4),
+            &palette,
+            |_, _, _| 1,
+            |_, _, _, _| UNIT_BRICK
+        ).unwrap();
+
+        let panels = mosaic.panels(4, 4, &[TWO_BY_TWO_PLATE], &[], make_base).unwrap();
+        let bill = panels[0].bill_of_materials();
+
+        let base_plates = panels[0].base().iter().filter(|brick| brick.brick == Brick::NonUnit(TWO_BY_TWO_PLATE)).count() as u32;
+        assert_eq!(4 + base_plates, bill.count(Brick::NonUnit(TWO_BY_TWO_PLATE), COLOR_1));
+        assert_eq!(bill.total(), (panels[0].base().iter().count() + panels[0].mosaic().iter().count()) as u32);
+    }
+
+    #[test]
+    fn test_bill_of_materials_counts_rotations_together() {
+        let mut bill = BillOfMaterials::new(vec![
+            PlacedBrick { l: 0, w: 0, h: 0, brick: Brick::NonUnit(TWO_BY_ONE_PLATE), color: COLOR_1 },
+            PlacedBrick { l: 0, w: 1, h: 0, brick: Brick::NonUnit(TWO_BY_ONE_PLATE.rotate_90()), color: COLOR_1 },
+            PlacedBrick { l: 0, w: 3, h: 0, brick: Brick::NonUnit(TWO_BY_ONE_PLATE), color: COLOR_2 },
+            PlacedBrick { l: 0, w: 4, h: 0, brick: Brick::<u8, TestBrick>::Unit(UNIT_BRICK), color: COLOR_2 }
+        ]);
+
+        assert_eq!(3, bill.entries().len());
+        assert_eq!(2, bill.count(Brick::NonUnit(TWO_BY_ONE_PLATE), COLOR_1));
+
+        bill.merge(&bill.clone());
+        assert_eq!(4, bill.count(Brick::NonUnit(TWO_BY_ONE_PLATE.rotate_90()), COLOR_1));
+        assert_eq!(8, bill.total());
+    }
+
+    #[test]
+    fn test_label_after_z() {
+        let (_, palette) = make_test_img();
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image(
+            &make_single_color_img(28, 1),
+            &palette,
+            |_, _, _| 1,
+            |_, _, _, _| UNIT_BRICK
+        ).unwrap();
+
+        let labels: Vec<String> = mosaic.panels(1, 1, &[], &[], make_base).unwrap().iter()
+            .map(|panel| panel.label())
+            .collect();
+
+        assert_eq!("Z1", labels[25]);
+        assert_eq!("AA1", labels[26]);
+        assert_eq!("AB1", labels[27]);
+    }
+
+    #[test]
+    fn test_zero_panel_size() {
+        let (img, palette) = make_test_img();
+        let mosaic: Mosaic<u8, TestBrick, TestColor> = Mosaic::from_image(&img, &palette, |_, _, _| 1, |_, _, _, _| UNIT_BRICK).unwrap();
+
+        assert_eq!(Err(PanelError::ZeroPanelSize), mosaic.panels(0, 4, &[], &[], make_base));
+    }
+}
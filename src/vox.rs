@@ -0,0 +1,435 @@
+use std::io::{ErrorKind, Read};
+use crate::{Color, Mosaic, MosaicError, NonUnitBrick, Palette, RawColor, ThreadSafe, UnitBrick, VoxelGrid};
+
+// MagicaVoxel uses a right-handed coordinate system with z pointing up. From above, x points
+// east and y points north. This module maps those axes onto the l, w, and h axes explicitly:
+//
+//     l = x
+//     w = size_y - 1 - y
+//     h = z
+//
+// A model therefore looks the same from above in MagicaVoxel and in the mosaic, rather than
+// being mirrored along the north-south axis.
+
+// ====================
+// PUBLIC STRUCTS
+// ====================
+
+#[non_exhaustive]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum VoxError {
+    Io(ErrorKind),
+    NotAVoxFile,
+    UnexpectedEnd,
+    MissingSize,
+    ModelTooLarge(u32, u32, u32),
+    VoxelOutsideModel(u8, u8, u8),
+    NoSuchModel(usize),
+    Mosaic(MosaicError)
+}
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct VoxModel {
+    length: u32,
+    width: u32,
+    height: u32,
+    color_indices: Vec<u8>
+}
+
+impl VoxModel {
+    pub fn length(&self) -> u32 {
+        self.length
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn color_index(&self, l: u32, w: u32, h: u32) -> Option<u8> {
+        if l >= self.length || w >= self.width || h >= self.height {
+            return None;
+        }
+
+        // Index zero is reserved for empty points
+        match self.color_indices[self.index(l, w, h)] {
+            0 => None,
+            index => Some(index)
+        }
+    }
+
+    fn index(&self, l: u32, w: u32, h: u32) -> usize {
+        (h as usize * self.width as usize + w as usize) * self.length as usize + l as usize
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Vox {
+    models: Vec<VoxModel>,
+    colors: Vec<RawColor>
+}
+
+impl Vox {
+    pub fn read(reader: &mut impl Read) -> Result<Self, VoxError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).map_err(|err| VoxError::Io(err.kind()))?;
+        Vox::from_bytes(&bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, VoxError> {
+        let mut reader = ByteReader { bytes, pos: 0 };
+
+        if reader.take(4)? != b"VOX " {
+            return Err(VoxError::NotAVoxFile);
+        }
+
+        // Every version so far uses the same layout for the chunks that are read here
+        reader.u32()?;
+
+        if reader.take(4)? != b"MAIN" {
+            return Err(VoxError::NotAVoxFile);
+        }
+        let main_content_size = reader.u32()? as usize;
+        let main_children_size = reader.u32()? as usize;
+        reader.take(main_content_size)?;
+
+        let mut children = ByteReader { bytes: reader.take(main_children_size)?, pos: 0 };
+        let mut models = Vec::new();
+        let mut colors = None;
+        let mut size = None;
+
+        /* Chunks that are not needed to build a model, such as the scene graph and materials,
+           are skipped. Models are read at their own origin, ignoring any scene transforms. */
+        while !children.is_empty() {
+            let id = children.take(4)?;
+            let content_size = children.u32()? as usize;
+            let children_size = children.u32()? as usize;
+            let mut content = ByteReader { bytes: children.take(content_size)?, pos: 0 };
+            children.take(children_size)?;
+
+            match id {
+                b"SIZE" => size = Some((content.u32()?, content.u32()?, content.u32()?)),
+                b"XYZI" => {
+                    let (size_x, size_y, size_z) = size.take().ok_or(VoxError::MissingSize)?;
+
+                    /* Voxel coordinates are stored in a byte, so no model can be larger than 256
+                       on any axis. Check the size before allocating the model so that a corrupt
+                       file cannot ask for an enormous buffer. */
+                    let volume = Some(size_x as usize)
+                        .filter(|_| size_x <= MAX_MODEL_SIZE && size_y <= MAX_MODEL_SIZE && size_z <= MAX_MODEL_SIZE)
+                        .and_then(|volume| volume.checked_mul(size_y as usize))
+                        .and_then(|volume| volume.checked_mul(size_z as usize))
+                        .ok_or(VoxError::ModelTooLarge(size_x, size_y, size_z))?;
+
+                    let mut model = VoxModel {
+                        length: size_x,
+                        width: size_y,
+                        height: size_z,
+                        color_indices: vec![0; volume]
+                    };
+
+                    for _ in 0..content.u32()? {
+                        let voxel = content.take(4)?;
+                        let (x, y, z, color_index) = (voxel[0], voxel[1], voxel[2], voxel[3]);
+
+                        if x as u32 >= size_x || y as u32 >= size_y || z as u32 >= size_z {
+                            return Err(VoxError::VoxelOutsideModel(x, y, z));
+                        }
+
+                        let index = model.index(x as u32, size_y - 1 - y as u32, z as u32);
+                        model.color_indices[index] = color_index;
+                    }
+
+                    models.push(model);
+                },
+                b"RGBA" => {
+
+                    // The file stores colors for indices 1 through 255 followed by an unused entry
+                    let mut file_colors = vec![RawColor::default()];
+                    for _ in 0..255 {
+                        let color = content.take(4)?;
+                        file_colors.push(RawColor { red: color[0], green: color[1], blue: color[2], alpha: color[3] });
+                    }
+
+                    colors = Some(file_colors);
+                },
+                _ => {}
+            }
+        }
+
+        Ok(Vox {
+            models,
+            colors: colors.unwrap_or_else(default_colors)
+        })
+    }
+
+    pub fn models(&self) -> &[VoxModel] {
+        &self.models
+    }
+
+    pub fn color(&self, index: u8) -> RawColor {
+        self.colors[index as usize]
+    }
+
+    pub fn to_mosaic<U: UnitBrick, B: NonUnitBrick<U>, C: Color>(&self,
+                                                                 model_index: usize,
+                                                                 palette: &impl Palette<C>,
+                                                                 brick_fn: impl Fn(u32, u32, u32, C) -> U + ThreadSafe) -> Result<Mosaic<U, B, C>, VoxError> {
+        let model = self.models.get(model_index).ok_or(VoxError::NoSuchModel(model_index))?;
+
+        // There are only 256 colors in a file, so find the nearest color for each one once
+        let colors = self.colors.iter().map(|&color| palette.nearest(color)).collect();
+
+        Mosaic::from_voxels(&MappedModel {
+            model,
+            colors,
+            brick_fn
+        }).map_err(VoxError::Mosaic)
+    }
+}
+
+// ====================
+// PRIVATE CONSTANTS
+// ====================
+
+const MAX_MODEL_SIZE: u32 = 256;
+
+// ====================
+// PRIVATE FUNCTIONS
+// ====================
+
+fn default_colors() -> Vec<RawColor> {
+    let mut colors = vec![RawColor::default()];
+    let cube_values = [0xFF, 0xCC, 0x99, 0x66, 0x33, 0x00];
+    let ramp_values = [0xEE, 0xDD, 0xBB, 0xAA, 0x88, 0x77, 0x55, 0x44, 0x22, 0x11];
+
+    /* MagicaVoxel's default palette starts with a 6x6x6 color cube, from white down to the
+       darkest blue, without black. It ends with ramps of red, green, blue, and gray. */
+    for red in cube_values {
+        for green in cube_values {
+            for blue in cube_values {
+                if red != 0 || green != 0 || blue != 0 {
+                    colors.push(RawColor { red, green, blue, alpha: 255 });
+                }
+            }
+        }
+    }
+
+    for value in ramp_values {
+        colors.push(RawColor { red: value, green: 0, blue: 0, alpha: 255 });
+    }
+
+    for value in ramp_values {
+        colors.push(RawColor { red: 0, green: value, blue: 0, alpha: 255 });
+    }
+
+    for value in ramp_values {
+        colors.push(RawColor { red: 0, green: 0, blue: value, alpha: 255 });
+    }
+
+    for value in ramp_values {
+        colors.push(RawColor { red: value, green: value, blue: value, alpha: 255 });
+    }
+
+    colors
+}
+
+// ====================
+// PRIVATE STRUCTS
+// ====================
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    pos: usize
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], VoxError> {
+        let end = self.pos.checked_add(count).filter(|&end| end <= self.bytes.len()).ok_or(VoxError::UnexpectedEnd)?;
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, VoxError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+}
+
+struct MappedModel<'a, C, F> {
+    model: &'a VoxModel,
+    colors: Vec<Option<C>>,
+    brick_fn: F
+}
+
+impl<U, C: Copy, F: Fn(u32, u32, u32, C) -> U> VoxelGrid<U, C> for MappedModel<'_, C, F> {
+    fn voxel(&self, l: u32, w: u32, h: u32) -> Option<(C, U)> {
+        let color = self.colors[self.model.color_index(l, w, h)? as usize]?;
+        Some((color, (self.brick_fn)(l, w, h, color)))
+    }
+
+    fn length(&self) -> u32 {
+        self.model.length
+    }
+
+    fn width(&self) -> u32 {
+        self.model.width
+    }
+
+    fn height(&self) -> u32 {
+        self.model.height
+    }
+}
+
+#[cfg(all(test, feature = "default", feature = "vox"))]
+mod tests {
+    use crate::{Mosaic, RawColor};
+    use crate::ldraw::{LdrawColor, RED, SOLID_COLORS, WHITE};
+    use crate::palette::Ciede2000Palette;
+    use crate::tests::{TestBrick, TWO_BY_ONE_PLATE, UNIT_BRICK};
+    use crate::vox::{Vox, VoxError};
+
+    fn chunk(id: &[u8], content: &[u8]) -> Vec<u8> {
+        let mut bytes = id.to_vec();
+        bytes.extend((content.len() as u32).to_le_bytes());
+        bytes.extend(0u32.to_le_bytes());
+        bytes.extend(content);
+        bytes
+    }
+
+    fn make_vox(size: (u32, u32, u32), voxels: &[[u8; 4]], colors: Option<&[[u8; 4]]>) -> Vec<u8> {
+        let mut size_content = Vec::new();
+        size_content.extend(size.0.to_le_bytes());
+        size_content.extend(size.1.to_le_bytes());
+        size_content.extend(size.2.to_le_bytes());
+
+        let mut xyzi_content = (voxels.len() as u32).to_le_bytes().to_vec();
+        for voxel in voxels {
+            xyzi_content.extend(voxel);
+        }
+
+        let mut children = chunk(b"PACK", &1u32.to_le_bytes());
+        children.extend(chunk(b"SIZE", &size_content));
+        children.extend(chunk(b"XYZI", &xyzi_content));
+
+        if let Some(colors) = colors {
+            let mut rgba_content = Vec::new();
+            for index in 0..256 {
+                rgba_content.extend(colors.get(index).copied().unwrap_or([0, 0, 0, 255]));
+            }
+            children.extend(chunk(b"RGBA", &rgba_content));
+        }
+
+        let mut bytes = b"VOX ".to_vec();
+        bytes.extend(150u32.to_le_bytes());
+        bytes.extend(b"MAIN");
+        bytes.extend(0u32.to_le_bytes());
+        bytes.extend((children.len() as u32).to_le_bytes());
+        bytes.extend(children);
+        bytes
+    }
+
+    #[test]
+    fn test_not_a_vox_file() {
+        assert_eq!(Err(VoxError::NotAVoxFile), Vox::from_bytes(b"PNG 1234"));
+    }
+
+    #[test]
+    fn test_truncated_file() {
+        let bytes = make_vox((1, 1, 1), &[[0, 0, 0, 1]], None);
+        assert_eq!(Err(VoxError::UnexpectedEnd), Vox::from_bytes(&bytes[..bytes.len() - 2]));
+    }
+
+    #[test]
+    fn test_voxel_outside_model() {
+        let bytes = make_vox((1, 1, 1), &[[0, 1, 0, 1]], None);
+        assert_eq!(Err(VoxError::VoxelOutsideModel(0, 1, 0)), Vox::from_bytes(&bytes));
+    }
+
+    #[test]
+    fn test_read_maps_axes() {
+        let bytes = make_vox((2, 3, 4), &[[1, 0, 3, 5], [0, 2, 0, 9]], None);
+        let vox = Vox::read(&mut bytes.as_slice()).unwrap();
+
+        assert_eq!(1, vox.models().len());
+        let model = &vox.models()[0];
+        assert_eq!(2, model.length());
+        assert_eq!(3, model.width());
+        assert_eq!(4, model.height());
+
+        // The y axis points north, so the first row in the file is the southernmost row
+        assert_eq!(Some(5), model.color_index(1, 2, 3));
+        assert_eq!(Some(9), model.color_index(0, 0, 0));
+        assert_eq!(None, model.color_index(0, 0, 1));
+        assert_eq!(None, model.color_index(2, 0, 0));
+    }
+
+    #[test]
+    fn test_default_colors() {
+        let vox = Vox::from_bytes(&make_vox((1, 1, 1), &[], None)).unwrap();
+
+        assert_eq!(RawColor { red: 255, green: 255, blue: 255, alpha: 255 }, vox.color(1));
+        assert_eq!(RawColor { red: 255, green: 255, blue: 204, alpha: 255 }, vox.color(2));
+        assert_eq!(RawColor { red: 0, green: 0, blue: 51, alpha: 255 }, vox.color(215));
+        assert_eq!(RawColor { red: 238, green: 0, blue: 0, alpha: 255 }, vox.color(216));
+        assert_eq!(RawColor { red: 17, green: 17, blue: 17, alpha: 255 }, vox.color(255));
+    }
+
+    #[test]
+    fn test_file_colors_map_to_palette() {
+        let colors = [[180, 0, 0, 255], [244, 244, 244, 255]];
+        let bytes = make_vox((3, 1, 2), &[[0, 0, 0, 1], [1, 0, 0, 1], [2, 0, 0, 2], [0, 0, 1, 2]], Some(&colors));
+        let vox = Vox::from_bytes(&bytes).unwrap();
+        let palette = Ciede2000Palette::new(SOLID_COLORS);
+
+        let mosaic: Mosaic<u8, TestBrick, LdrawColor> = vox.to_mosaic(0, &palette, |_, _, _, _| UNIT_BRICK)
+            .and_then(|mosaic| mosaic.reduce_bricks(&[TWO_BY_ONE_PLATE], &[]).map_err(VoxError::Mosaic))
+            .unwrap();
+
+        let mut bricks: Vec<_> = mosaic.iter().map(|brick| (brick.l, brick.w, brick.h, brick.brick.length(), brick.color)).collect();
+        bricks.sort_by_key(|&(l, w, h, _, _)| (h, l, w));
+
+        assert_eq!(vec![(0, 0, 0, 2, RED), (2, 0, 0, 1, WHITE), (0, 0, 1, 1, WHITE)], bricks);
+    }
+
+    #[test]
+    fn test_empty_model() {
+        let vox = Vox::from_bytes(&make_vox((4, 4, 4), &[], None)).unwrap();
+        let palette = Ciede2000Palette::new(SOLID_COLORS);
+
+        let mosaic: Result<Mosaic<u8, TestBrick, LdrawColor>, VoxError> = vox.to_mosaic(0, &palette, |_, _, _, _| UNIT_BRICK);
+        assert_eq!(0, mosaic.unwrap().iter().count());
+    }
+
+    #[test]
+    fn test_model_too_large() {
+        let bytes = make_vox((257, 1, 1), &[], None);
+        assert_eq!(Err(VoxError::ModelTooLarge(257, 1, 1)), Vox::from_bytes(&bytes));
+
+        let bytes = make_vox((u32::MAX, u32::MAX, u32::MAX), &[], None);
+        assert_eq!(Err(VoxError::ModelTooLarge(u32::MAX, u32::MAX, u32::MAX)), Vox::from_bytes(&bytes));
+    }
+
+    #[test]
+    fn test_largest_model() {
+        let vox = Vox::from_bytes(&make_vox((256, 256, 1), &[[255, 255, 0, 1]], None)).unwrap();
+        assert_eq!(Some(1), vox.models()[0].color_index(255, 0, 0));
+    }
+
+    #[test]
+    fn test_no_such_model() {
+        let vox = Vox::from_bytes(&make_vox((1, 1, 1), &[], None)).unwrap();
+        let palette = Ciede2000Palette::new(SOLID_COLORS);
+
+        let mosaic: Result<Mosaic<u8, TestBrick, LdrawColor>, VoxError> = vox.to_mosaic(1, &palette, |_, _, _, _| UNIT_BRICK);
+        assert_eq!(Err(VoxError::NoSuchModel(1)), mosaic);
+    }
+}